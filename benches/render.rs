@@ -0,0 +1,149 @@
+//! Frame times of the editor canvas on an annotated 8K screenshot, drawing
+//! everything from the pixbuf each frame against painting the cached layer.
+//!
+//! Run with `cargo bench --bench render`.
+
+use gtk4::gdk_pixbuf::{Colorspace, Pixbuf};
+use gtk4::prelude::GdkCairoContextExt;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// The editor lives in the binary, so the modules the renderer needs are
+// compiled in here directly. Their unit tests are compiled out without the
+// test harness, which leaves the imports of those tests unused.
+#[allow(dead_code, unused_imports)]
+#[path = "../src/editor"]
+mod editor {
+    pub mod render;
+    pub mod shapes;
+    pub mod smoothing;
+}
+
+use editor::render::{image_surface, RenderCache};
+use editor::shapes::{Color, Shape, ShapeType};
+
+const WIDTH: i32 = 7680;
+const HEIGHT: i32 = 4320;
+const SHAPES: usize = 200;
+const FRAMES: u32 = 20;
+
+/// A 1080p window's worth of the canvas, as when scrolled around at 100%.
+const VIEWPORT: (f64, f64, f64, f64) = (2000.0, 1000.0, 1920.0, 1080.0);
+
+fn main() {
+    let pixbuf = Pixbuf::new(Colorspace::Rgb, false, 8, WIDTH, HEIGHT).expect("8K pixbuf");
+    pixbuf.fill(0x336699ff);
+    let shapes = annotations();
+    let stroke = stroke_in_progress();
+
+    let target = cairo::ImageSurface::create(cairo::Format::ARgb32, WIDTH, HEIGHT).unwrap();
+
+    bench("uncached, full canvas", || {
+        let ctx = cairo::Context::new(&target).unwrap();
+        ctx.set_source_pixbuf(&pixbuf, 0.0, 0.0);
+        ctx.paint().unwrap();
+        for shape in &shapes {
+            shape.draw(&ctx);
+        }
+        stroke.draw(&ctx);
+    });
+
+    bench("base image surface", || {
+        black_box(image_surface(&pixbuf));
+    });
+
+    let mut cache = RenderCache::default();
+    cache.set_image(Some(&pixbuf));
+    bench("layer rebuild", || {
+        cache.set_image(Some(&pixbuf));
+        black_box(cache.layer(&shapes, 0, 1.0, false));
+    });
+
+    cache.layer(&shapes, 0, 1.0, false);
+    bench("cached, full canvas", || {
+        let ctx = cairo::Context::new(&target).unwrap();
+        ctx.set_source_surface(cache.layer(&shapes, 0, 1.0, false).unwrap(), 0.0, 0.0)
+            .unwrap();
+        ctx.paint().unwrap();
+        stroke.draw(&ctx);
+    });
+
+    bench("cached, visible area", || {
+        let ctx = cairo::Context::new(&target).unwrap();
+        let (x, y, width, height) = VIEWPORT;
+        ctx.rectangle(x, y, width, height);
+        ctx.clip();
+        ctx.set_source_surface(cache.layer(&shapes, 0, 1.0, false).unwrap(), 0.0, 0.0)
+            .unwrap();
+        ctx.paint().unwrap();
+        stroke.draw(&ctx);
+    });
+}
+
+fn bench(name: &str, mut frame: impl FnMut()) {
+    frame();
+
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        frame();
+    }
+    let average = start.elapsed() / FRAMES;
+
+    println!("{:<24} {:>8.2} ms/frame", name, as_millis(average));
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// A spread of committed annotations: boxes, arrows, highlights and
+/// freehand strokes.
+fn annotations() -> Vec<Shape> {
+    (0..SHAPES)
+        .map(|i| {
+            let x = (i % 20) as f64 * 380.0 + 20.0;
+            let y = (i / 20) as f64 * 420.0 + 20.0;
+            let shape_type = match i % 4 {
+                0 => ShapeType::Rectangle,
+                1 => ShapeType::Arrow,
+                2 => ShapeType::Highlight,
+                _ => ShapeType::FreeHand,
+            };
+            let points = (0..100)
+                .map(|step| {
+                    let t = step as f64 / 99.0;
+                    (x + t * 300.0, y + (t * 12.0).sin() * 80.0 + 150.0)
+                })
+                .collect();
+            Shape {
+                shape_type,
+                start_x: x,
+                start_y: y,
+                end_x: x + 300.0,
+                end_y: y + 300.0,
+                color: Color::new(1.0, 0.0, 0.0, 1.0),
+                stroke_width: 4.0,
+                points,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// The stroke under the pointer, redrawn on every motion event.
+fn stroke_in_progress() -> Shape {
+    Shape {
+        shape_type: ShapeType::FreeHand,
+        color: Color::new(0.0, 1.0, 0.0, 1.0),
+        stroke_width: 6.0,
+        points: (0..400)
+            .map(|step| {
+                (
+                    2000.0 + step as f64 * 4.0,
+                    1500.0 + (step as f64 / 20.0).cos() * 200.0,
+                )
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
@@ -1,18 +1,45 @@
 use gtk4::gdk::RGBA;
-use gtk4::glib;
 use gtk4::prelude::*;
+use gtk4::{gio, glib};
 use gtk4::{
-    Application, ApplicationWindow, Box, Button, ColorButton, CssProvider, EventControllerKey,
-    Orientation, Overlay, Scale, ScrolledWindow, Separator, ToggleButton,
+    Application, ApplicationWindow, Box, Button, CheckButton, ColorDialog, ColorDialogButton,
+    CssProvider, DropDown, FileDialog, Grid, Label, MenuButton, NamedAction, Orientation, Overlay,
+    Popover, Scale, ScrolledWindow, Separator, Shortcut, ShortcutController, ShortcutTrigger,
+    SpinButton, ToggleButton,
 };
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::Duration;
 
 use crate::capture::{CaptureBackend, CaptureMode};
-use crate::editor::{Color, EditorCanvas, ToolType};
+use crate::clipboard;
+use crate::commands::{self, COMMANDS};
+use crate::config::{Config, ImageFormat};
+use crate::editor::{
+    tool_stroke_width, Alignment, Color, ColorSwatches, DashStyle, Distribution, EditorCanvas,
+    FillMode, LayersPanel, Minimap, PropertiesPanel, StatusBar, StrokeCap, StrokeJoin, ToolType,
+    ZOrder,
+};
+use crate::export::{self, ExportOptions, PngCompression};
+use crate::notify::{NotificationAction, Notifier};
+use crate::palette::{self, PaletteEntry};
+use crate::session::{Autosave, Session};
+use crate::toast::Toasts;
 
 const APP_ID: &str = "com.github.niri-shot";
 
+/// Seconds between autosaves of unsaved annotations.
+const AUTOSAVE_INTERVAL: u32 = 5;
+
+/// Quiet time after the last tool style change before it is saved, so
+/// dragging the stroke width slider writes once.
+const TOOL_STYLE_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Action that only needs the canvas.
+type CanvasAction = fn(&EditorCanvas);
+
 pub struct NiriShotApp {
     app: Application,
 }
@@ -25,9 +52,10 @@ impl NiriShotApp {
             .build();
 
         let initial_data = Rc::new(RefCell::new(initial_data));
+        let notifier = Notifier::new();
 
         app.connect_activate(move |app| {
-            Self::build_ui(app, initial_data.clone());
+            Self::build_ui(app, initial_data.clone(), notifier.clone());
         });
 
         Self { app }
@@ -72,6 +100,45 @@ impl NiriShotApp {
             .capture-bar button {
                 padding: 6px 12px;
             }
+
+            .layers-panel,
+            .properties-panel,
+            .shortcuts,
+            .command-palette,
+            .save-options {
+                padding: 8px;
+            }
+
+            .toast {
+                background: rgba(40, 40, 40, 0.95);
+                border-radius: 12px;
+                padding: 6px 12px;
+            }
+
+            .minimap {
+                border: 1px solid rgba(255, 255, 255, 0.4);
+                box-shadow: 0 2px 6px rgba(0, 0, 0, 0.5);
+            }
+
+            .toast.error {
+                background: rgba(140, 30, 30, 0.95);
+            }
+
+            .swatch {
+                padding: 3px;
+                min-width: 0;
+                min-height: 0;
+            }
+
+            .recent-colors {
+                margin-left: 6px;
+            }
+
+            .status-bar {
+                padding: 2px 8px;
+                font-size: smaller;
+                font-feature-settings: "tnum";
+            }
             "#,
         );
 
@@ -82,9 +149,16 @@ impl NiriShotApp {
         );
     }
 
-    fn build_ui(app: &Application, initial_data: Rc<RefCell<Option<Vec<u8>>>>) {
+    fn build_ui(
+        app: &Application,
+        initial_data: Rc<RefCell<Option<Vec<u8>>>>,
+        notifier: Rc<Notifier>,
+    ) {
         Self::load_css();
 
+        let config = Rc::new(RefCell::new(Config::load()));
+        let keybindings = config.borrow().keybindings.clone();
+
         let window = ApplicationWindow::builder()
             .application(app)
             .title("niri-shot")
@@ -100,6 +174,16 @@ impl NiriShotApp {
         let canvas = EditorCanvas::new();
         canvas.set_hexpand(true);
         canvas.set_vexpand(true);
+        canvas.set_grid_size(config.borrow().snap_grid_size);
+        canvas.set_pixel_grid(config.borrow().pixel_grid);
+        canvas.set_pixel_snap(
+            config
+                .borrow()
+                .pixel_snap_tools
+                .iter()
+                .filter_map(|name| Self::tool_from_name(name))
+                .collect(),
+        );
 
         let scrolled = ScrolledWindow::new();
         scrolled.set_child(Some(&canvas));
@@ -109,7 +193,10 @@ impl NiriShotApp {
         let overlay = Overlay::new();
         overlay.set_child(Some(&scrolled));
 
-        let floating_toolbar = Self::create_floating_toolbar();
+        let swatches = ColorSwatches::new(&config.borrow().palette);
+        swatches.set_recent(&Self::recent_colors());
+
+        let floating_toolbar = Self::create_floating_toolbar(swatches.widget());
         floating_toolbar.add_css_class("floating-toolbar");
         floating_toolbar.set_halign(gtk4::Align::Center);
         floating_toolbar.set_valign(gtk4::Align::End);
@@ -117,25 +204,241 @@ impl NiriShotApp {
 
         overlay.add_overlay(&floating_toolbar);
 
+        let minimap = Minimap::new(&canvas, &scrolled);
+        minimap.set_enabled(config.borrow().minimap);
+        overlay.add_overlay(minimap.widget());
+
+        let toasts = Toasts::new();
+        overlay.add_overlay(toasts.widget());
+
+        let layers_panel = LayersPanel::new(&canvas);
+        window.add_action(&gio::PropertyAction::new(
+            "toggle-layers",
+            layers_panel.widget(),
+            "visible",
+        ));
+
+        let properties_panel = PropertiesPanel::new(&canvas);
+
+        let side_panel = Box::new(Orientation::Vertical, 0);
+        side_panel.append(properties_panel.widget());
+        side_panel.append(layers_panel.widget());
+
+        let status_bar = StatusBar::new(&canvas);
+
+        let canvas_box = Box::new(Orientation::Vertical, 0);
+        canvas_box.append(&overlay);
+        canvas_box.append(&Separator::new(Orientation::Horizontal));
+        canvas_box.append(status_bar.widget());
+
+        let content_box = Box::new(Orientation::Horizontal, 0);
+        content_box.append(&canvas_box);
+        content_box.append(&side_panel);
+
         main_box.append(&capture_bar);
         main_box.append(&Separator::new(Orientation::Horizontal));
-        main_box.append(&overlay);
+        main_box.append(&content_box);
 
-        Self::connect_capture_buttons(&capture_bar, &canvas, &window, &floating_toolbar);
+        Self::install_actions(&window, &canvas, &config, &toasts, &notifier);
+        Self::install_minimap_action(&window, &minimap, &config, &toasts);
+        Self::install_shortcuts(&window, &keybindings, &toasts);
         Self::connect_tool_buttons(&floating_toolbar, &canvas);
-        Self::connect_action_buttons(&floating_toolbar, &canvas);
-        Self::setup_keyboard_shortcuts(&window, &canvas);
+        // Set while the toolbar shows the selected shape's style, which is
+        // not the user picking a color or width.
+        let syncing_style = Rc::new(Cell::new(false));
+        Self::connect_toolbar_to_selection(&floating_toolbar, &canvas, &syncing_style);
+        Self::connect_color_picked(&floating_toolbar, &canvas, &toasts);
+        Self::restore_tool_style(&floating_toolbar);
+        Self::connect_tool_style_memory(&floating_toolbar, &swatches, &syncing_style, &toasts);
+        Self::install_color_actions(&window, &floating_toolbar, &swatches);
+        Self::connect_style_menu(&floating_toolbar, &canvas);
+        Self::connect_style_menu_to_tool(&window, &floating_toolbar);
+
+        let mut actionables = Self::get_children(&capture_bar);
+        actionables.extend(Self::get_children(&floating_toolbar));
+        actionables.extend(Self::menu_controls(&floating_toolbar, "arrange_menu"));
+        Self::set_action_tooltips(&actionables, &keybindings);
+
+        let autosave = Rc::new(Autosave::new());
+        Self::start_autosave(&canvas, &autosave, &toasts);
 
         window.set_child(Some(&main_box));
+        Self::connect_close_prompt(&window, &canvas, &autosave, &toasts, &notifier);
 
         let data = initial_data.borrow_mut().take();
         window.present();
 
         if let Some(image_data) = data {
             canvas.set_image(&image_data);
-            Self::enable_action_buttons(&floating_toolbar, true);
+            Self::enable_image_actions(&window, true);
             Self::resize_window_to_image(&window, &canvas);
+            Self::notify_captured(&notifier, &window, &image_data);
         }
+
+        Self::offer_restore(&window, &canvas, &toasts);
+    }
+
+    /// Every few seconds, writes the image and annotations to the cache
+    /// while there are unsaved changes, so they survive a crash.
+    fn start_autosave(canvas: &EditorCanvas, autosave: &Rc<Autosave>, toasts: &Toasts) {
+        let pending = Rc::new(Cell::new(false));
+        let changed = pending.clone();
+        canvas.connect_shapes_changed(move |_| changed.set(true));
+
+        let canvas = canvas.downgrade();
+        let autosave = autosave.clone();
+        let toasts = toasts.clone();
+        glib::timeout_add_seconds_local(AUTOSAVE_INTERVAL, move || {
+            let Some(canvas) = canvas.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            if !canvas.is_dirty() {
+                autosave.clear();
+                return glib::ControlFlow::Continue;
+            }
+            if !pending.replace(false) {
+                return glib::ControlFlow::Continue;
+            }
+
+            let (Some(image), Ok(shapes)) = (canvas.image(), canvas.shapes_json()) else {
+                return glib::ControlFlow::Continue;
+            };
+            if let Err(e) = autosave.write(&image, &shapes) {
+                toasts.error("Autosave failed", &e);
+            }
+            glib::ControlFlow::Continue
+        });
+    }
+
+    /// Closing with unsaved annotations asks what to do with them first.
+    fn connect_close_prompt(
+        window: &ApplicationWindow,
+        canvas: &EditorCanvas,
+        autosave: &Rc<Autosave>,
+        toasts: &Toasts,
+        notifier: &Rc<Notifier>,
+    ) {
+        let canvas = canvas.clone();
+        let autosave = autosave.clone();
+        let toasts = toasts.clone();
+        let notifier = notifier.clone();
+        window.connect_close_request(move |window| {
+            if canvas.is_dirty() {
+                Self::confirm_discard(
+                    window,
+                    &canvas,
+                    &toasts,
+                    &notifier,
+                    ("Save changes before closing?", "Copy & Close"),
+                    |window| window.close(),
+                );
+                return glib::Propagation::Stop;
+            }
+
+            autosave.clear();
+            if let Some(app) = window.application() {
+                clipboard::keep_alive(&app);
+            }
+            glib::Propagation::Proceed
+        });
+    }
+
+    /// Asks whether to save, copy or discard unsaved annotations, then runs
+    /// `proceed` unless the user cancelled or saving or copying failed.
+    /// `labels` are the question and the label of the copy button.
+    fn confirm_discard(
+        window: &ApplicationWindow,
+        canvas: &EditorCanvas,
+        toasts: &Toasts,
+        notifier: &Rc<Notifier>,
+        (message, copy_label): (&str, &str),
+        proceed: impl FnOnce(&ApplicationWindow) + 'static,
+    ) {
+        let dialog = gtk4::AlertDialog::builder()
+            .modal(true)
+            .message(message)
+            .detail("Your annotations will be lost if you don't save or copy them.")
+            .buttons(["Cancel", "Discard", copy_label, "Save"])
+            .cancel_button(0)
+            .default_button(3)
+            .build();
+
+        let window_weak = window.downgrade();
+        let canvas = canvas.clone();
+        let toasts = toasts.clone();
+        let notifier = notifier.clone();
+        dialog.choose(Some(window), gio::Cancellable::NONE, move |result| {
+            let Some(window) = window_weak.upgrade() else {
+                return;
+            };
+            match result {
+                Ok(1) => canvas.mark_clean(),
+                Ok(2) => {
+                    if Self::copy_image(&canvas, None, &toasts, &notifier) {
+                        canvas.mark_clean();
+                    }
+                }
+                // A successful save marks the canvas clean.
+                Ok(3) => {
+                    let _ = WidgetExt::activate_action(&window, "win.save", None);
+                }
+                _ => return,
+            }
+            if !canvas.is_dirty() {
+                proceed(&window);
+            }
+        });
+    }
+
+    /// Offers the autosave of an editor that did not close cleanly.
+    fn offer_restore(window: &ApplicationWindow, canvas: &EditorCanvas, toasts: &Toasts) {
+        let Some(session) = Session::find_orphaned() else {
+            return;
+        };
+
+        let mut detail = String::from("niri-shot did not close cleanly last time.");
+        if let Some(modified) = session.modified {
+            let modified = chrono::DateTime::<chrono::Local>::from(modified);
+            detail.push_str(&format!(
+                " Restore the screenshot and annotations autosaved at {}?",
+                modified.format("%H:%M on %b %e")
+            ));
+        }
+        if canvas.image().is_some() {
+            detail.push_str(" This replaces the current screenshot.");
+        }
+
+        let dialog = gtk4::AlertDialog::builder()
+            .modal(true)
+            .message("Restore unsaved screenshot?")
+            .detail(detail)
+            .buttons(["Later", "Discard", "Restore"])
+            .cancel_button(0)
+            .default_button(2)
+            .build();
+
+        let window_weak = window.downgrade();
+        let canvas = canvas.clone();
+        let toasts = toasts.clone();
+        dialog.choose(
+            Some(window),
+            gio::Cancellable::NONE,
+            move |result| match result {
+                Ok(1) => session.remove(),
+                Ok(2) => {
+                    canvas.set_image(&session.image);
+                    if let Err(e) = canvas.load_shapes_json(&session.shapes) {
+                        toasts.error("Could not restore annotations", &e);
+                    }
+                    if let Some(window) = window_weak.upgrade() {
+                        Self::enable_image_actions(&window, true);
+                        Self::resize_window_to_image(&window, &canvas);
+                    }
+                    session.remove();
+                }
+                _ => {}
+            },
+        );
     }
 
     fn create_capture_bar() -> Box {
@@ -146,55 +449,47 @@ impl NiriShotApp {
         bar.set_margin_end(16);
 
         let btn_fullscreen = Button::from_icon_name("view-fullscreen-symbolic");
-        btn_fullscreen.set_widget_name("btn_fullscreen");
-        btn_fullscreen.set_tooltip_text(Some("Fullscreen"));
+        btn_fullscreen.set_detailed_action_name("win.capture::fullscreen");
 
         let btn_region = Button::from_icon_name("edit-select-all-symbolic");
-        btn_region.set_widget_name("btn_region");
-        btn_region.set_tooltip_text(Some("Region"));
+        btn_region.set_detailed_action_name("win.capture::region");
 
         let btn_window = Button::from_icon_name("window-symbolic");
-        btn_window.set_widget_name("btn_window");
-        btn_window.set_tooltip_text(Some("Window"));
+        btn_window.set_detailed_action_name("win.capture::window");
+
+        let spacer = Box::new(Orientation::Horizontal, 0);
+        spacer.set_hexpand(true);
+
+        let btn_layers = ToggleButton::new();
+        btn_layers.set_icon_name("view-list-symbolic");
+        btn_layers.set_detailed_action_name("win.toggle-layers");
 
         bar.append(&btn_fullscreen);
         bar.append(&btn_region);
         bar.append(&btn_window);
+        bar.append(&spacer);
+        bar.append(&btn_layers);
 
         bar
     }
 
-    fn create_floating_toolbar() -> Box {
+    fn create_floating_toolbar(swatches: &Box) -> Box {
         let bar = Box::new(Orientation::Horizontal, 6);
 
-        let btn_select = Self::create_nerd_button("󰍽", "tool_select", "Select");
-        btn_select.set_active(true);
-
-        let btn_rect = Self::create_nerd_button("□", "tool_rectangle", "Rectangle");
-        btn_rect.set_group(Some(&btn_select));
-
-        let btn_circle = Self::create_nerd_button("○", "tool_circle", "Circle");
-        btn_circle.set_group(Some(&btn_select));
-
-        let btn_line = Self::create_nerd_button("╱", "tool_line", "Line");
-        btn_line.set_group(Some(&btn_select));
-
-        let btn_arrow = Self::create_nerd_button("󰁕", "tool_arrow", "Arrow");
-        btn_arrow.set_group(Some(&btn_select));
-
-        let btn_freehand = Self::create_nerd_button("󰏬", "tool_freehand", "Freehand");
-        btn_freehand.set_group(Some(&btn_select));
-
-        let btn_text = Self::create_nerd_button("󰊄", "tool_text", "Text");
-        btn_text.set_group(Some(&btn_select));
-
-        let btn_blur = Self::create_nerd_button("󰂵", "tool_blur", "Blur");
-        btn_blur.set_group(Some(&btn_select));
-
-        let btn_highlight = Self::create_nerd_button("󰸱", "tool_highlight", "Highlight");
-        btn_highlight.set_group(Some(&btn_select));
-
-        let color_btn = ColorButton::with_rgba(&RGBA::new(1.0, 0.0, 0.0, 1.0));
+        let btn_select = Self::create_nerd_button("󰍽", "win.tool::select");
+        let btn_rect = Self::create_nerd_button("□", "win.tool::rectangle");
+        let btn_circle = Self::create_nerd_button("○", "win.tool::circle");
+        let btn_line = Self::create_nerd_button("╱", "win.tool::line");
+        let btn_arrow = Self::create_nerd_button("󰁕", "win.tool::arrow");
+        let btn_freehand = Self::create_nerd_button("󰏬", "win.tool::freehand");
+        let btn_text = Self::create_nerd_button("󰊄", "win.tool::text");
+        let btn_blur = Self::create_nerd_button("󰂵", "win.tool::blur");
+        let btn_highlight = Self::create_nerd_button("󰸱", "win.tool::highlight");
+        let btn_marker = Self::create_nerd_button("󰙒", "win.tool::marker");
+        let btn_eyedropper = Self::create_nerd_button("󰈊", "win.tool::eyedropper");
+
+        let color_btn = ColorDialogButton::new(Some(ColorDialog::new()));
+        color_btn.set_rgba(&RGBA::new(1.0, 0.0, 0.0, 1.0));
         color_btn.set_widget_name("color_picker");
         color_btn.set_tooltip_text(Some("Color"));
 
@@ -204,17 +499,26 @@ impl NiriShotApp {
         stroke_scale.set_widget_name("stroke_width");
         stroke_scale.set_tooltip_text(Some("Stroke Width"));
 
-        let btn_zoom_out = Self::create_nerd_action_button("󰍴", "btn_zoom_out", "Zoom - (Ctrl+-)");
-        let btn_zoom_in = Self::create_nerd_action_button("󰍷", "btn_zoom_in", "Zoom + (Ctrl++)");
+        let smoothing_scale = Scale::with_range(Orientation::Horizontal, 0.0, 1.0, 0.1);
+        smoothing_scale.set_value(0.5);
+        smoothing_scale.set_width_request(60);
+        smoothing_scale.set_widget_name("smoothing");
+        smoothing_scale.set_tooltip_text(Some("Freehand Smoothing"));
 
-        let btn_undo = Self::create_nerd_action_button("󰕌", "btn_undo", "Undo (Ctrl+Z)");
-        let btn_redo = Self::create_nerd_action_button("󰑎", "btn_redo", "Redo (Ctrl+Y)");
+        let btn_insert_image = Self::create_nerd_action_button("󰋩", "win.insert-image");
 
-        let btn_save = Self::create_nerd_action_button("󰆓", "btn_save", "Save (Ctrl+S)");
-        btn_save.set_sensitive(false);
+        let style_menu = Self::create_style_menu();
+        let arrange_menu = Self::create_arrange_menu();
 
-        let btn_copy = Self::create_nerd_action_button("󰆏", "btn_copy", "Copy (Ctrl+C)");
-        btn_copy.set_sensitive(false);
+        let btn_zoom_out = Self::create_nerd_action_button("󰍴", "win.zoom-out");
+        let btn_zoom_in = Self::create_nerd_action_button("󰍷", "win.zoom-in");
+        let btn_zoom_fit = Self::create_nerd_action_button("󰁌", "win.zoom-fit");
+
+        let btn_undo = Self::create_nerd_action_button("󰕌", "win.undo");
+        let btn_redo = Self::create_nerd_action_button("󰑎", "win.redo");
+
+        let btn_save = Self::create_nerd_action_button("󰆓", "win.save");
+        let btn_copy = Self::create_nerd_action_button("󰆏", "win.copy-image");
 
         bar.append(&btn_select);
         bar.append(&btn_rect);
@@ -225,12 +529,20 @@ impl NiriShotApp {
         bar.append(&btn_text);
         bar.append(&btn_blur);
         bar.append(&btn_highlight);
+        bar.append(&btn_marker);
+        bar.append(&btn_eyedropper);
+        bar.append(&btn_insert_image);
         bar.append(&Separator::new(Orientation::Vertical));
         bar.append(&color_btn);
+        bar.append(swatches);
         bar.append(&stroke_scale);
+        bar.append(&smoothing_scale);
+        bar.append(&style_menu);
+        bar.append(&arrange_menu);
         bar.append(&Separator::new(Orientation::Vertical));
         bar.append(&btn_zoom_out);
         bar.append(&btn_zoom_in);
+        bar.append(&btn_zoom_fit);
         bar.append(&Separator::new(Orientation::Vertical));
         bar.append(&btn_undo);
         bar.append(&btn_redo);
@@ -241,79 +553,1048 @@ impl NiriShotApp {
         bar
     }
 
-    fn create_nerd_button(icon: &str, widget_name: &str, tooltip: &str) -> ToggleButton {
+    fn create_style_menu() -> MenuButton {
+        let grid = Box::new(Orientation::Vertical, 4);
+
+        let fill_mode = DropDown::from_strings(&FillMode::ALL.map(FillMode::label));
+        fill_mode.set_widget_name("fill_mode");
+
+        let fill_color = ColorDialogButton::new(Some(ColorDialog::new()));
+        fill_color.set_rgba(&RGBA::new(1.0, 0.0, 0.0, 1.0));
+        fill_color.set_widget_name("fill_color");
+
+        let opacity = Scale::with_range(Orientation::Horizontal, 0.0, 1.0, 0.05);
+        opacity.set_value(1.0);
+        opacity.set_width_request(120);
+        opacity.set_widget_name("opacity");
+
+        let dash = DropDown::from_strings(&DashStyle::ALL.map(DashStyle::label));
+        dash.set_widget_name("dash");
+
+        let line_cap = DropDown::from_strings(&StrokeCap::ALL.map(StrokeCap::label));
+        line_cap.set_widget_name("line_cap");
+
+        let line_join = DropDown::from_strings(&StrokeJoin::ALL.map(StrokeJoin::label));
+        line_join.set_widget_name("line_join");
+
+        let corner_radius = SpinButton::with_range(0.0, 100.0, 1.0);
+        corner_radius.set_widget_name("corner_radius");
+
+        let rows: [(&str, &[&gtk4::Widget]); 5] = [
+            ("Fill", &[fill_mode.upcast_ref(), fill_color.upcast_ref()]),
+            ("Opacity", &[opacity.upcast_ref()]),
+            ("Line", &[dash.upcast_ref()]),
+            ("Ends", &[line_cap.upcast_ref(), line_join.upcast_ref()]),
+            ("Corners", &[corner_radius.upcast_ref()]),
+        ];
+
+        for (title, controls) in rows {
+            let line = Box::new(Orientation::Horizontal, 4);
+            let label = Label::new(Some(title));
+            label.set_width_chars(8);
+            label.set_xalign(0.0);
+            line.append(&label);
+            for control in controls {
+                line.append(*control);
+            }
+            grid.append(&line);
+        }
+
+        let popover = Popover::new();
+        popover.set_child(Some(&grid));
+
+        let menu = MenuButton::new();
+        menu.set_label("󰏘");
+        menu.set_widget_name("style_menu");
+        menu.set_tooltip_text(Some("Shape Style"));
+        menu.add_css_class("nerd-icon");
+        menu.set_popover(Some(&popover));
+        menu
+    }
+
+    fn create_arrange_menu() -> MenuButton {
+        let grid = Box::new(Orientation::Vertical, 4);
+
+        let rows: [&[&str]; 3] = [
+            &["align-left", "align-center", "align-right"],
+            &["align-top", "align-middle", "align-bottom"],
+            &[
+                "distribute-horizontal",
+                "distribute-vertical",
+                "group",
+                "ungroup",
+            ],
+        ];
+
+        for row in rows {
+            let line = Box::new(Orientation::Horizontal, 4);
+            for command in row.iter().filter_map(|&id| commands::get(id)) {
+                let btn = Button::with_label(command.title);
+                btn.set_detailed_action_name(command.action);
+                line.append(&btn);
+            }
+            grid.append(&line);
+        }
+
+        let popover = Popover::new();
+        popover.set_child(Some(&grid));
+
+        let menu = MenuButton::new();
+        menu.set_label("󰀽");
+        menu.set_widget_name("arrange_menu");
+        menu.set_tooltip_text(Some("Arrange Selection"));
+        menu.add_css_class("nerd-icon");
+        menu.set_popover(Some(&popover));
+        menu
+    }
+
+    fn create_nerd_button(icon: &str, action: &str) -> ToggleButton {
         let btn = ToggleButton::with_label(icon);
-        btn.set_widget_name(widget_name);
-        btn.set_tooltip_text(Some(tooltip));
+        btn.set_detailed_action_name(action);
         btn.add_css_class("nerd-icon");
         btn
     }
 
-    fn create_nerd_action_button(icon: &str, widget_name: &str, tooltip: &str) -> Button {
+    fn create_nerd_action_button(icon: &str, action: &str) -> Button {
         let btn = Button::with_label(icon);
-        btn.set_widget_name(widget_name);
-        btn.set_tooltip_text(Some(tooltip));
+        btn.set_detailed_action_name(action);
         btn.add_css_class("nerd-icon");
         btn
     }
 
-    fn connect_capture_buttons(
-        capture_bar: &Box,
+    /// Registers every editor operation as a window action, so toolbar
+    /// buttons, keyboard shortcuts and menus all go through the same path.
+    fn install_actions(
+        window: &ApplicationWindow,
         canvas: &EditorCanvas,
+        config: &Rc<RefCell<Config>>,
+        toasts: &Toasts,
+        notifier: &Rc<Notifier>,
+    ) {
+        let keybindings = config.borrow().keybindings.clone();
+        let canvas_actions: [(&str, CanvasAction); 27] = [
+            ("undo", EditorCanvas::undo),
+            ("redo", EditorCanvas::redo),
+            ("cut", |canvas| {
+                canvas.cut_selected();
+            }),
+            ("paste", EditorCanvas::paste),
+            ("duplicate", EditorCanvas::duplicate_selected),
+            ("select-all", EditorCanvas::select_all),
+            ("delete", EditorCanvas::delete_selected),
+            ("cancel", EditorCanvas::cancel),
+            ("group", EditorCanvas::group_selected),
+            ("ungroup", EditorCanvas::ungroup_selected),
+            ("bring-forward", |canvas| {
+                canvas.reorder_selected(ZOrder::Forward)
+            }),
+            ("send-backward", |canvas| {
+                canvas.reorder_selected(ZOrder::Backward)
+            }),
+            ("bring-to-front", |canvas| {
+                canvas.reorder_selected(ZOrder::Front)
+            }),
+            ("send-to-back", |canvas| {
+                canvas.reorder_selected(ZOrder::Back)
+            }),
+            ("align-left", |canvas| {
+                canvas.align_selected(Alignment::Left)
+            }),
+            ("align-center", |canvas| {
+                canvas.align_selected(Alignment::CenterHorizontal)
+            }),
+            ("align-right", |canvas| {
+                canvas.align_selected(Alignment::Right)
+            }),
+            ("align-top", |canvas| canvas.align_selected(Alignment::Top)),
+            ("align-middle", |canvas| {
+                canvas.align_selected(Alignment::CenterVertical)
+            }),
+            ("align-bottom", |canvas| {
+                canvas.align_selected(Alignment::Bottom)
+            }),
+            ("distribute-horizontal", |canvas| {
+                canvas.distribute_selected(Distribution::Horizontal)
+            }),
+            ("distribute-vertical", |canvas| {
+                canvas.distribute_selected(Distribution::Vertical)
+            }),
+            ("zoom-in", EditorCanvas::zoom_in),
+            ("zoom-out", EditorCanvas::zoom_out),
+            ("zoom-reset", EditorCanvas::zoom_reset),
+            ("zoom-fit", EditorCanvas::zoom_to_fit),
+            ("zoom-fit-width", EditorCanvas::zoom_to_fit_width),
+        ];
+
+        for (name, run) in canvas_actions {
+            let canvas = canvas.clone();
+            Self::add_action(window, name, move || run(&canvas));
+        }
+
+        let tool = gio::SimpleAction::new_stateful(
+            "tool",
+            Some(glib::VariantTy::STRING),
+            &"select".to_variant(),
+        );
+        let tool_canvas = canvas.clone();
+        tool.connect_change_state(move |action, value| {
+            let Some(value) = value else {
+                return;
+            };
+            if let Some(tool) = value.str().and_then(Self::tool_from_name) {
+                tool_canvas.set_tool_type(tool);
+                action.set_state(value);
+            }
+        });
+        window.add_action(&tool);
+
+        let capture = gio::SimpleAction::new("capture", Some(glib::VariantTy::STRING));
+        let window_weak = window.downgrade();
+        let capture_canvas = canvas.clone();
+        let capture_toasts = toasts.clone();
+        let capture_notifier = notifier.clone();
+        capture.connect_activate(move |_, value| {
+            let mode = match value.and_then(|v| v.str()) {
+                Some("fullscreen") => CaptureMode::Fullscreen,
+                Some("region") => CaptureMode::Region,
+                Some("window") => CaptureMode::Window,
+                _ => return,
+            };
+            if let Some(window) = window_weak.upgrade() {
+                Self::capture(
+                    &window,
+                    &capture_canvas,
+                    mode,
+                    &capture_toasts,
+                    &capture_notifier,
+                );
+            }
+        });
+        window.add_action(&capture);
+
+        // File the current image was last saved to, offered along with the
+        // image when copying. Any edit makes it stale.
+        let saved_path: Rc<RefCell<Option<PathBuf>>> = Rc::default();
+        let stale_path = saved_path.clone();
+        canvas.connect_shapes_changed(move |_| {
+            stale_path.borrow_mut().take();
+        });
+
+        let copy_canvas = canvas.clone();
+        let copy_path = saved_path.clone();
+        let copy_toasts = toasts.clone();
+        let copy_notifier = notifier.clone();
+        Self::add_action(window, "copy", move || {
+            if !copy_canvas.copy_selected() {
+                Self::copy_image(
+                    &copy_canvas,
+                    copy_path.borrow().as_deref(),
+                    &copy_toasts,
+                    &copy_notifier,
+                );
+            }
+        });
+
+        let copy_canvas = canvas.clone();
+        let copy_path = saved_path.clone();
+        let copy_toasts = toasts.clone();
+        let copy_notifier = notifier.clone();
+        Self::add_action(window, "copy-image", move || {
+            Self::copy_image(
+                &copy_canvas,
+                copy_path.borrow().as_deref(),
+                &copy_toasts,
+                &copy_notifier,
+            );
+        });
+
+        let save_canvas = canvas.clone();
+        let save_config = config.clone();
+        let save_path = saved_path.clone();
+        let save_toasts = toasts.clone();
+        let save_notifier = notifier.clone();
+        Self::add_action(window, "save", move || {
+            let config = save_config.borrow();
+            let path = Self::quick_save_path(&config, config.default_format);
+            let options = ExportOptions::new(config.default_format);
+            Self::save_image(
+                &save_canvas,
+                &path,
+                &options,
+                &save_path,
+                &save_toasts,
+                &save_notifier,
+            );
+        });
+
+        let export = gio::SimpleAction::new("export", Some(glib::VariantTy::STRING));
+        let export_canvas = canvas.clone();
+        let export_config = config.clone();
+        let export_path = saved_path.clone();
+        let export_toasts = toasts.clone();
+        let export_notifier = notifier.clone();
+        export.connect_activate(move |_, value| {
+            if let Some(format) = value
+                .and_then(|v| v.str())
+                .and_then(ImageFormat::from_extension)
+            {
+                let path = Self::quick_save_path(&export_config.borrow(), format);
+                let options = ExportOptions::new(format);
+                Self::save_image(
+                    &export_canvas,
+                    &path,
+                    &options,
+                    &export_path,
+                    &export_toasts,
+                    &export_notifier,
+                );
+            }
+        });
+        window.add_action(&export);
+
+        let window_weak = window.downgrade();
+        let save_as_canvas = canvas.clone();
+        let save_as_config = config.clone();
+        let save_as_toasts = toasts.clone();
+        let save_as_notifier = notifier.clone();
+        Self::add_action(window, "save-as", move || {
+            let Some(window) = window_weak.upgrade() else {
+                return;
+            };
+            let canvas = save_as_canvas.clone();
+            let saved_path = saved_path.clone();
+            let toasts = save_as_toasts.clone();
+            let notifier = save_as_notifier.clone();
+            let dialog_toasts = toasts.clone();
+            Self::save_as_dialog(
+                &window,
+                &save_as_config,
+                &dialog_toasts,
+                move |path, options| {
+                    Self::save_image(&canvas, &path, &options, &saved_path, &toasts, &notifier);
+                },
+            );
+        });
+
+        let open_folder = gio::SimpleAction::new("open-folder", Some(glib::VariantTy::STRING));
+        let window_weak = window.downgrade();
+        let open_folder_toasts = toasts.clone();
+        open_folder.connect_activate(move |_, value| {
+            if let Some(path) = value.and_then(|v| v.str()) {
+                Self::open_containing_folder(
+                    window_weak.upgrade().as_ref(),
+                    Path::new(path),
+                    &open_folder_toasts,
+                );
+            }
+        });
+        window.add_action(&open_folder);
+
+        let copy_path = gio::SimpleAction::new("copy-path", Some(glib::VariantTy::STRING));
+        copy_path.connect_activate(|_, value| {
+            if let (Some(display), Some(path)) =
+                (gtk4::gdk::Display::default(), value.and_then(|v| v.str()))
+            {
+                display.clipboard().set_text(path);
+            }
+        });
+        window.add_action(&copy_path);
+
+        let zoom_to = gio::SimpleAction::new("zoom-to", Some(glib::VariantTy::DOUBLE));
+        let zoom_canvas = canvas.clone();
+        zoom_to.connect_activate(move |_, value| {
+            if let Some(zoom) = value.and_then(|v| v.get::<f64>()) {
+                zoom_canvas.set_zoom(zoom);
+            }
+        });
+        window.add_action(&zoom_to);
+
+        let pixel_grid = gio::SimpleAction::new_stateful(
+            "toggle-pixel-grid",
+            None,
+            &config.borrow().pixel_grid.to_variant(),
+        );
+        let grid_canvas = canvas.clone();
+        let grid_config = config.clone();
+        let grid_toasts = toasts.clone();
+        pixel_grid.connect_change_state(move |action, value| {
+            let Some(visible) = value.and_then(|v| v.get::<bool>()) else {
+                return;
+            };
+            action.set_state(&visible.to_variant());
+            grid_canvas.set_pixel_grid(visible);
+
+            let mut config = grid_config.borrow_mut();
+            config.pixel_grid = visible;
+            if let Err(e) = config.save() {
+                grid_toasts.error("Could not save settings", &e);
+            }
+        });
+        window.add_action(&pixel_grid);
+
+        let open = gio::SimpleAction::new("open", Some(glib::VariantTy::STRING));
+        let window_weak = window.downgrade();
+        let open_canvas = canvas.clone();
+        let open_toasts = toasts.clone();
+        let open_notifier = notifier.clone();
+        open.connect_activate(move |_, value| {
+            let (Some(window), Some(path)) = (window_weak.upgrade(), value.and_then(|v| v.str()))
+            else {
+                return;
+            };
+            let canvas = open_canvas.clone();
+            let toasts = open_toasts.clone();
+            let notifier = open_notifier.clone();
+            let path = PathBuf::from(path);
+            let open_file = move |window: &ApplicationWindow| match std::fs::read(&path) {
+                Ok(data) => {
+                    canvas.set_image(&data);
+                    Self::enable_image_actions(window, true);
+                    Self::resize_window_to_image(window, &canvas);
+                }
+                Err(e) => {
+                    toasts.error("Could not open screenshot", &e);
+                    notifier.error("Could not open screenshot", &e);
+                }
+            };
+
+            // Opening replaces the annotations and their undo history.
+            if open_canvas.is_dirty() {
+                Self::confirm_discard(
+                    &window,
+                    &open_canvas,
+                    &open_toasts,
+                    &open_notifier,
+                    (
+                        "Save changes before opening another screenshot?",
+                        "Copy & Open",
+                    ),
+                    open_file,
+                );
+            } else {
+                open_file(&window);
+            }
+        });
+        window.add_action(&open);
+
+        let window_weak = window.downgrade();
+        let insert_canvas = canvas.clone();
+        let insert_toasts = toasts.clone();
+        Self::add_action(window, "insert-image", move || {
+            let window = window_weak.upgrade();
+            Self::insert_image_dialog(
+                window.as_ref().map(|w| w.upcast_ref()),
+                &insert_canvas,
+                &insert_toasts,
+            );
+        });
+
+        let window_weak = window.downgrade();
+        let shortcuts_keybindings = keybindings.clone();
+        Self::add_action(window, "show-shortcuts", move || {
+            if let Some(window) = window_weak.upgrade() {
+                Self::show_shortcuts_window(&window, &shortcuts_keybindings);
+            }
+        });
+
+        let window_weak = window.downgrade();
+        let palette_toasts = toasts.clone();
+        Self::add_action(window, "command-palette", move || {
+            if let Some(window) = window_weak.upgrade() {
+                let entries = Self::palette_entries(&window, &keybindings);
+                palette::show(&window, entries, &palette_toasts);
+            }
+        });
+
+        Self::enable_image_actions(window, false);
+    }
+
+    fn install_minimap_action(
         window: &ApplicationWindow,
-        toolbar: &Box,
+        minimap: &Rc<Minimap>,
+        config: &Rc<RefCell<Config>>,
+        toasts: &Toasts,
     ) {
-        let buttons: Vec<_> = Self::get_children(capture_bar)
-            .into_iter()
-            .filter_map(|w| w.downcast::<Button>().ok())
-            .collect();
+        let action = gio::SimpleAction::new_stateful(
+            "toggle-minimap",
+            None,
+            &config.borrow().minimap.to_variant(),
+        );
+        let minimap = minimap.clone();
+        let config = config.clone();
+        let toasts = toasts.clone();
+        action.connect_change_state(move |action, value| {
+            let Some(enabled) = value.and_then(|v| v.get::<bool>()) else {
+                return;
+            };
+            action.set_state(&enabled.to_variant());
+            minimap.set_enabled(enabled);
+
+            let mut config = config.borrow_mut();
+            config.minimap = enabled;
+            if let Err(e) = config.save() {
+                toasts.error("Could not save settings", &e);
+            }
+        });
+        window.add_action(&action);
+    }
 
-        for btn in buttons {
-            let canvas = canvas.clone();
-            let window = window.clone();
-            let toolbar = toolbar.clone();
-
-            btn.connect_clicked(move |button| {
-                let mode = match button.widget_name().as_str() {
-                    "btn_fullscreen" => CaptureMode::Fullscreen,
-                    "btn_region" => CaptureMode::Region,
-                    "btn_window" => CaptureMode::Window,
-                    _ => return,
+    fn add_action(window: &ApplicationWindow, name: &str, run: impl Fn() + 'static) {
+        let action = gio::SimpleAction::new(name, None);
+        action.connect_activate(move |_, _| run());
+        window.add_action(&action);
+    }
+
+    /// Binds the accelerators of every command. The controller runs in the
+    /// bubble phase, so text entries and the text tool get keys first.
+    fn install_shortcuts(
+        window: &ApplicationWindow,
+        keybindings: &HashMap<String, Vec<String>>,
+        toasts: &Toasts,
+    ) {
+        let controller = ShortcutController::new();
+
+        for command in COMMANDS {
+            let Ok((name, target)) = gio::Action::parse_detailed_name(command.action) else {
+                continue;
+            };
+
+            for accel in commands::accels(command, keybindings) {
+                let Some(trigger) = ShortcutTrigger::parse_string(accel) else {
+                    toasts.error(&format!("Invalid keybinding for {}", command.id), &accel);
+                    continue;
                 };
+                let shortcut = Shortcut::new(Some(trigger), Some(NamedAction::new(&name)));
+                shortcut.set_arguments(target.as_ref());
+                controller.add_shortcut(shortcut);
+            }
+        }
 
-                window.set_visible(false);
+        window.add_controller(controller);
+    }
 
-                while glib::MainContext::default().iteration(false) {}
-                std::thread::sleep(std::time::Duration::from_millis(150));
+    /// Tooltips of action buttons: the command title and its first shortcut.
+    fn set_action_tooltips(widgets: &[gtk4::Widget], keybindings: &HashMap<String, Vec<String>>) {
+        for widget in widgets {
+            let Some(actionable) = widget.dynamic_cast_ref::<gtk4::Actionable>() else {
+                continue;
+            };
+            let Some(name) = actionable.action_name() else {
+                continue;
+            };
+            let target = actionable.action_target_value();
+            let Some(command) = commands::find(
+                name.trim_start_matches("win."),
+                target.as_ref().and_then(|v| v.str()),
+            ) else {
+                continue;
+            };
+
+            let accel = commands::accels(command, keybindings)
+                .first()
+                .and_then(|accel| ShortcutTrigger::parse_string(accel))
+                .map(|trigger| trigger.to_label(&widget.display()));
+            let tooltip = match accel {
+                Some(accel) => format!("{} ({})", command.title, accel),
+                None => command.title.to_string(),
+            };
+            widget.set_tooltip_text(Some(&tooltip));
+        }
+    }
 
-                let result = CaptureBackend::capture(mode);
+    /// Lists every command with its shortcuts, grouped by section.
+    fn show_shortcuts_window(
+        parent: &ApplicationWindow,
+        keybindings: &HashMap<String, Vec<String>>,
+    ) {
+        let display = WidgetExt::display(parent);
+        let content = Box::new(Orientation::Vertical, 12);
+        content.add_css_class("shortcuts");
+
+        let mut sections: Vec<&str> = Vec::new();
+        for command in COMMANDS {
+            if !sections.contains(&command.section) {
+                sections.push(command.section);
+            }
+        }
 
-                window.set_visible(true);
+        for section in sections {
+            let title = Label::new(Some(section));
+            title.set_halign(gtk4::Align::Start);
+            title.add_css_class("heading");
+
+            let grid = Grid::new();
+            grid.set_column_spacing(24);
+            grid.set_row_spacing(4);
+
+            let section_commands = COMMANDS.iter().filter(|c| c.section == section);
+            for (row, command) in section_commands.enumerate() {
+                let accels: Vec<String> = commands::accels(command, keybindings)
+                    .into_iter()
+                    .filter_map(ShortcutTrigger::parse_string)
+                    .map(|trigger| trigger.to_label(&display).to_string())
+                    .collect();
+
+                let name = Label::new(Some(command.title));
+                name.set_xalign(0.0);
+                name.set_hexpand(true);
+                let keys = Label::new(Some(&accels.join(", ")));
+                keys.set_xalign(1.0);
+                keys.add_css_class("dim-label");
+
+                grid.attach(&name, 0, row as i32, 1, 1);
+                grid.attach(&keys, 1, row as i32, 1, 1);
+            }
 
-                match result {
-                    Ok(data) => {
-                        canvas.set_image(&data);
-                        Self::enable_action_buttons(&toolbar, true);
-                        Self::resize_window_to_image(&window, &canvas);
+            content.append(&title);
+            content.append(&grid);
+        }
+
+        let scrolled = ScrolledWindow::new();
+        scrolled.set_child(Some(&content));
+        scrolled.set_propagate_natural_width(true);
+
+        let window = gtk4::Window::builder()
+            .title("Keyboard Shortcuts")
+            .transient_for(parent)
+            .modal(true)
+            .default_height(600)
+            .child(&scrolled)
+            .build();
+        window.present();
+    }
+
+    /// Every enabled command, followed by the recently saved files.
+    fn palette_entries(
+        window: &ApplicationWindow,
+        keybindings: &HashMap<String, Vec<String>>,
+    ) -> Vec<PaletteEntry> {
+        let display = WidgetExt::display(window);
+        let mut entries = Vec::new();
+
+        for command in COMMANDS {
+            let enabled = gio::Action::parse_detailed_name(command.action)
+                .ok()
+                .and_then(|(name, _)| window.lookup_action(name.trim_start_matches("win.")))
+                .is_some_and(|action| action.is_enabled());
+            if !enabled || command.id == "command-palette" {
+                continue;
+            }
+
+            let accel = commands::accels(command, keybindings)
+                .first()
+                .and_then(|accel| ShortcutTrigger::parse_string(accel))
+                .map(|trigger| trigger.to_label(&display).to_string());
+
+            entries.push(PaletteEntry {
+                title: command.title.to_string(),
+                section: command.section.to_string(),
+                accel,
+                action: command.action.to_string(),
+            });
+        }
+
+        for path in Config::load_recent_files() {
+            let path = path.display().to_string();
+            entries.push(PaletteEntry {
+                title: format!("Open {}", path),
+                section: "Recent".to_string(),
+                accel: None,
+                action: gio::Action::print_detailed_name("win.open", Some(&path.to_variant()))
+                    .to_string(),
+            });
+        }
+
+        entries
+    }
+
+    fn tool_from_name(name: &str) -> Option<ToolType> {
+        let tool = match name {
+            "select" => ToolType::Select,
+            "rectangle" => ToolType::Rectangle,
+            "circle" => ToolType::Circle,
+            "line" => ToolType::Line,
+            "arrow" => ToolType::Arrow,
+            "freehand" => ToolType::FreeHand,
+            "text" => ToolType::Text,
+            "blur" => ToolType::Blur,
+            "highlight" => ToolType::Highlight,
+            "marker" => ToolType::Marker,
+            "eyedropper" => ToolType::Eyedropper,
+            _ => return None,
+        };
+        Some(tool)
+    }
+
+    fn capture(
+        window: &ApplicationWindow,
+        canvas: &EditorCanvas,
+        mode: CaptureMode,
+        toasts: &Toasts,
+        notifier: &Notifier,
+    ) {
+        window.set_visible(false);
+
+        while glib::MainContext::default().iteration(false) {}
+        std::thread::sleep(std::time::Duration::from_millis(150));
+
+        let result = CaptureBackend::capture(mode);
+
+        window.set_visible(true);
+
+        match result {
+            Ok(data) => {
+                canvas.set_image(&data);
+                Self::enable_image_actions(window, true);
+                Self::resize_window_to_image(window, canvas);
+                Self::notify_captured(notifier, window, &data);
+            }
+            Err(e) => {
+                toasts.error("Capture failed", &e);
+                notifier.error("Capture failed", &e);
+            }
+        }
+    }
+
+    fn notify_captured(notifier: &Notifier, window: &ApplicationWindow, data: &[u8]) {
+        let window = window.downgrade();
+        notifier.notify(
+            "Screenshot captured",
+            "Annotate it in the editor, or paste it anywhere.",
+            Some(data),
+            vec![NotificationAction::new("Open in editor", move || {
+                if let Some(window) = window.upgrade() {
+                    window.present();
+                }
+            })],
+        );
+    }
+
+    fn open_containing_folder(parent: Option<&ApplicationWindow>, path: &Path, toasts: &Toasts) {
+        let launcher = gtk4::FileLauncher::new(Some(&gio::File::for_path(path)));
+        let toasts = toasts.clone();
+        launcher.open_containing_folder(parent, gio::Cancellable::NONE, move |result| {
+            if let Err(e) = result {
+                toasts.error("Could not open folder", &e);
+            }
+        });
+    }
+
+    fn save_image(
+        canvas: &EditorCanvas,
+        path: &Path,
+        options: &ExportOptions,
+        saved: &Rc<RefCell<Option<PathBuf>>>,
+        toasts: &Toasts,
+        notifier: &Rc<Notifier>,
+    ) {
+        let Some(data) = canvas.get_image_data() else {
+            return;
+        };
+        if let Err(e) = export::write(path, &data, options) {
+            toasts.error("Save failed", &e);
+            notifier.error("Save failed", &e);
+            return;
+        }
+        canvas.mark_clean();
+
+        if let Err(e) = Config::add_recent_file(path) {
+            toasts.error("Could not update recent files", &e);
+        }
+        *saved.borrow_mut() = Some(path.to_path_buf());
+
+        let target = path.display().to_string().to_variant();
+        toasts.show(
+            &format!("Saved to {}", path.display()),
+            &[
+                (
+                    "Open folder",
+                    gio::Action::print_detailed_name("win.open-folder", Some(&target)).into(),
+                ),
+                (
+                    "Copy path",
+                    gio::Action::print_detailed_name("win.copy-path", Some(&target)).into(),
+                ),
+            ],
+        );
+
+        let folder_path = path.to_path_buf();
+        let delete_path = path.to_path_buf();
+        let delete_saved = saved.clone();
+        let delete_notifier = notifier.clone();
+        let folder_toasts = toasts.clone();
+        notifier.notify(
+            "Screenshot saved",
+            &path.display().to_string(),
+            Some(&data),
+            vec![
+                NotificationAction::new("Open folder", move || {
+                    Self::open_containing_folder(None, &folder_path, &folder_toasts);
+                }),
+                NotificationAction::new("Delete", move || {
+                    if let Err(e) = std::fs::remove_file(&delete_path) {
+                        delete_notifier.error("Could not delete screenshot", &e);
+                        return;
+                    }
+                    let mut saved = delete_saved.borrow_mut();
+                    if saved.as_deref() == Some(delete_path.as_path()) {
+                        saved.take();
+                    }
+                }),
+            ],
+        );
+    }
+
+    /// Where Ctrl+S writes: the configured folder and file name template.
+    fn quick_save_path(config: &Config, format: ImageFormat) -> PathBuf {
+        config
+            .save_directory
+            .join(Self::default_file_name(config, format))
+    }
+
+    fn default_file_name(config: &Config, format: ImageFormat) -> String {
+        let stem = chrono::Local::now().format(&config.filename_template);
+        format!("{}.{}", stem, format.extension())
+    }
+
+    /// Asks for the format, quality and scale, then for the destination,
+    /// and hands both to `on_chosen`.
+    fn save_as_dialog(
+        parent: &ApplicationWindow,
+        config: &Rc<RefCell<Config>>,
+        toasts: &Toasts,
+        on_chosen: impl Fn(PathBuf, ExportOptions) + 'static,
+    ) {
+        let (default_format, remembered) = {
+            let config = config.borrow();
+            (config.default_format, config.save_as_directory.clone())
+        };
+
+        let format = DropDown::from_strings(&ImageFormat::ALL.map(ImageFormat::label));
+        let format_position = ImageFormat::ALL.iter().position(|&f| f == default_format);
+        format.set_selected(format_position.unwrap_or(0) as u32);
+
+        let quality = SpinButton::with_range(1.0, 100.0, 1.0);
+        quality.set_value(ExportOptions::new(default_format).quality as f64);
+
+        let compression = DropDown::from_strings(&PngCompression::ALL.map(PngCompression::label));
+        let compression_position = PngCompression::ALL
+            .iter()
+            .position(|&c| c == PngCompression::default());
+        compression.set_selected(compression_position.unwrap_or(0) as u32);
+
+        let scale = SpinButton::with_range(10.0, 400.0, 25.0);
+        scale.set_value(100.0);
+
+        let remember = CheckButton::with_label("Remember this folder");
+        remember.set_active(remembered.is_some());
+
+        let update_sensitivity = {
+            let quality = quality.clone();
+            let compression = compression.clone();
+            move |format: &DropDown| {
+                let jpeg =
+                    ImageFormat::ALL.get(format.selected() as usize) == Some(&ImageFormat::Jpg);
+                quality.set_sensitive(jpeg);
+                compression.set_sensitive(!jpeg);
+            }
+        };
+        update_sensitivity(&format);
+        format.connect_selected_notify(update_sensitivity);
+
+        let grid = Grid::new();
+        grid.set_column_spacing(12);
+        grid.set_row_spacing(6);
+        let rows: [(&str, &gtk4::Widget); 4] = [
+            ("Format", format.upcast_ref()),
+            ("JPEG quality", quality.upcast_ref()),
+            ("PNG compression", compression.upcast_ref()),
+            ("Scale (%)", scale.upcast_ref()),
+        ];
+        for (row, (title, control)) in rows.into_iter().enumerate() {
+            let label = Label::new(Some(title));
+            label.set_xalign(0.0);
+            grid.attach(&label, 0, row as i32, 1, 1);
+            grid.attach(control, 1, row as i32, 1, 1);
+        }
+        grid.attach(&remember, 0, rows.len() as i32, 2, 1);
+
+        let cancel = Button::with_label("Cancel");
+        let choose = Button::with_label("Save…");
+        choose.add_css_class("suggested-action");
+
+        let buttons = Box::new(Orientation::Horizontal, 6);
+        buttons.set_halign(gtk4::Align::End);
+        buttons.append(&cancel);
+        buttons.append(&choose);
+
+        let content = Box::new(Orientation::Vertical, 12);
+        content.add_css_class("save-options");
+        content.append(&grid);
+        content.append(&buttons);
+
+        let window = gtk4::Window::builder()
+            .title("Save As")
+            .transient_for(parent)
+            .modal(true)
+            .resizable(false)
+            .child(&content)
+            .build();
+
+        let cancel_window = window.downgrade();
+        cancel.connect_clicked(move |_| {
+            if let Some(window) = cancel_window.upgrade() {
+                window.close();
+            }
+        });
+
+        let parent = parent.clone();
+        let config = config.clone();
+        let toasts = toasts.clone();
+        let on_chosen = Rc::new(on_chosen);
+        let options_window = window.downgrade();
+        choose.connect_clicked(move |_| {
+            let format = ImageFormat::ALL
+                .get(format.selected() as usize)
+                .copied()
+                .unwrap_or(default_format);
+            let options = ExportOptions {
+                format,
+                quality: quality.value() as u8,
+                compression: PngCompression::ALL
+                    .get(compression.selected() as usize)
+                    .copied()
+                    .unwrap_or_default(),
+                scale: scale.value() / 100.0,
+            };
+            let remember = remember.is_active();
+
+            if let Some(window) = options_window.upgrade() {
+                window.close();
+            }
+
+            let folder = {
+                let config = config.borrow();
+                config
+                    .save_as_directory
+                    .clone()
+                    .unwrap_or_else(|| config.save_directory.clone())
+            };
+            let name = Self::default_file_name(&config.borrow(), format);
+
+            let config = config.clone();
+            let toasts = toasts.clone();
+            let on_chosen = on_chosen.clone();
+            let on_path = Rc::new(move |path: PathBuf| {
+                let mut options = options;
+                if let Some(chosen) = ImageFormat::from_path(&path) {
+                    options.format = chosen;
+                }
+
+                {
+                    let mut config = config.borrow_mut();
+                    let directory = path.parent().filter(|_| remember).map(Path::to_path_buf);
+                    if config.save_as_directory != directory {
+                        config.save_as_directory = directory;
+                        if let Err(e) = config.save() {
+                            toasts.error("Could not save settings", &e);
+                        }
                     }
-                    Err(e) => eprintln!("Capture error: {}", e),
                 }
+
+                on_chosen(path, options);
             });
+            Self::choose_save_path(&parent, &folder, &name, format, on_path);
+        });
+
+        window.present();
+    }
+
+    /// Asks where to save. A name typed without an image extension gets the
+    /// one for `format`; if a file by that name exists, the dialog comes back
+    /// with the full name so overwriting it still has to be confirmed there.
+    fn choose_save_path(
+        parent: &ApplicationWindow,
+        folder: &Path,
+        name: &str,
+        format: ImageFormat,
+        on_path: Rc<dyn Fn(PathBuf)>,
+    ) {
+        let dialog = FileDialog::builder()
+            .title("Save As")
+            .modal(true)
+            .initial_folder(&gio::File::for_path(folder))
+            .initial_name(name)
+            .build();
+
+        let window = parent.clone();
+        dialog.save(Some(parent), gio::Cancellable::NONE, move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            if ImageFormat::from_path(&path).is_some() {
+                on_path(path);
+                return;
+            }
+
+            let mut name = path.file_name().unwrap_or_default().to_os_string();
+            name.push(".");
+            name.push(format.extension());
+            let path = path.with_file_name(&name);
+            if !path.exists() {
+                on_path(path);
+                return;
+            }
+
+            let folder = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            Self::choose_save_path(&window, &folder, &name.to_string_lossy(), format, on_path);
+        });
+    }
+
+    fn copy_image(
+        canvas: &EditorCanvas,
+        saved: Option<&Path>,
+        toasts: &Toasts,
+        notifier: &Notifier,
+    ) -> bool {
+        let Some(data) = canvas.get_image_data() else {
+            return false;
+        };
+        match clipboard::copy_image(&data, saved) {
+            Ok(()) => {
+                toasts.show("Copied to clipboard", &[]);
+                notifier.notify("Copied to clipboard", "", Some(&data), Vec::new());
+                true
+            }
+            Err(e) => {
+                toasts.error("Copy failed", &e);
+                notifier.error("Copy failed", &e);
+                false
+            }
         }
     }
 
+    /// Sizes the window for the image at 100%, up to most of the monitor.
+    /// Bigger images are zoomed out to fit by the canvas.
     fn resize_window_to_image(window: &ApplicationWindow, canvas: &EditorCanvas) {
-        let img_width = canvas.content_width();
-        let img_height = canvas.content_height();
-
-        if img_width <= 0 || img_height <= 0 {
+        let Some((img_width, img_height)) = canvas.image_size() else {
             return;
-        }
+        };
+
+        let display = WidgetExt::display(window);
+        let monitor = window
+            .surface()
+            .and_then(|surface| display.monitor_at_surface(&surface))
+            .or_else(|| display.monitors().item(0).and_downcast());
+        let (max_width, max_height) = match monitor {
+            Some(monitor) => {
+                let geometry = monitor.geometry();
+                (geometry.width() * 9 / 10, geometry.height() * 9 / 10)
+            }
+            None => (1600, 1000),
+        };
 
         let toolbar_height = 100;
-        let max_width = 1600;
-        let max_height = 1000;
-
         let width = (img_width + 40).min(max_width);
         let height = (img_height + toolbar_height).min(max_height);
 
@@ -324,32 +1605,7 @@ impl NiriShotApp {
         let children = Self::get_children(toolbar);
 
         for widget in children {
-            if let Ok(toggle) = widget.clone().downcast::<ToggleButton>() {
-                let canvas = canvas.clone();
-
-                toggle.connect_toggled(move |button| {
-                    if !button.is_active() {
-                        return;
-                    }
-
-                    let tool = match button.widget_name().as_str() {
-                        "tool_select" => ToolType::Select,
-                        "tool_rectangle" => ToolType::Rectangle,
-                        "tool_circle" => ToolType::Circle,
-                        "tool_line" => ToolType::Line,
-                        "tool_arrow" => ToolType::Arrow,
-                        "tool_freehand" => ToolType::FreeHand,
-                        "tool_text" => ToolType::Text,
-                        "tool_blur" => ToolType::Blur,
-                        "tool_highlight" => ToolType::Highlight,
-                        _ => return,
-                    };
-
-                    canvas.set_tool_type(tool);
-                });
-            }
-
-            if let Ok(color_btn) = widget.clone().downcast::<ColorButton>() {
+            if let Ok(color_btn) = widget.clone().downcast::<ColorDialogButton>() {
                 let canvas = canvas.clone();
 
                 color_btn.connect_rgba_notify(move |button| {
@@ -364,118 +1620,400 @@ impl NiriShotApp {
                 });
             }
 
-            if let Ok(scale) = widget.clone().downcast::<Scale>() {
-                if scale.widget_name() == "stroke_width" {
-                    let canvas = canvas.clone();
-
-                    scale.connect_value_changed(move |s| {
-                        canvas.set_stroke_width(s.value());
-                    });
-                }
-            }
-
-            if let Ok(btn) = widget.downcast::<Button>() {
+            if let Ok(scale) = widget.downcast::<Scale>() {
                 let canvas = canvas.clone();
 
-                btn.connect_clicked(move |button| match button.widget_name().as_str() {
-                    "btn_undo" => canvas.undo(),
-                    "btn_redo" => canvas.redo(),
-                    "btn_zoom_in" => canvas.zoom_in(),
-                    "btn_zoom_out" => canvas.zoom_out(),
+                match scale.widget_name().as_str() {
+                    "stroke_width" => {
+                        scale.connect_value_changed(move |s| {
+                            canvas.set_stroke_width(s.value());
+                        });
+                    }
+                    "smoothing" => {
+                        scale.connect_value_changed(move |s| {
+                            canvas.set_smoothing(s.value());
+                        });
+                    }
                     _ => {}
-                });
+                }
             }
         }
     }
 
-    fn connect_action_buttons(toolbar: &Box, canvas: &EditorCanvas) {
-        let buttons: Vec<_> = Self::get_children(toolbar)
+    /// Shows the style of a single selected shape on the toolbar, so it also
+    /// becomes the default for the next shape.
+    fn connect_toolbar_to_selection(
+        toolbar: &Box,
+        canvas: &EditorCanvas,
+        syncing: &Rc<Cell<bool>>,
+    ) {
+        let children = Self::get_children(toolbar);
+        let color_btn = children
+            .iter()
+            .find_map(|w| w.clone().downcast::<ColorDialogButton>().ok());
+        let stroke_scale = children
+            .iter()
+            .filter_map(|w| w.clone().downcast::<Scale>().ok())
+            .find(|scale| scale.widget_name() == "stroke_width");
+        let style_controls = Self::menu_controls(toolbar, "style_menu");
+        let syncing = syncing.clone();
+
+        canvas.connect_selection_changed(move |canvas| {
+            if canvas.selection().len() != 1 {
+                return;
+            }
+            let Some(shape) = canvas.selected_shape() else {
+                return;
+            };
+
+            syncing.set(true);
+            if let Some(ref btn) = color_btn {
+                let color = shape.color;
+                btn.set_rgba(&RGBA::new(
+                    color.r as f32,
+                    color.g as f32,
+                    color.b as f32,
+                    color.a as f32,
+                ));
+            }
+            if let Some(ref scale) = stroke_scale {
+                scale.set_value(tool_stroke_width(&shape));
+            }
+            syncing.set(false);
+
+            for widget in &style_controls {
+                if let Some(dropdown) = widget.downcast_ref::<DropDown>() {
+                    let position = match dropdown.widget_name().as_str() {
+                        "fill_mode" => FillMode::ALL.iter().position(|&f| f == shape.fill),
+                        "dash" => DashStyle::ALL.iter().position(|&d| d == shape.dash),
+                        "line_cap" => StrokeCap::ALL.iter().position(|&c| c == shape.cap),
+                        "line_join" => StrokeJoin::ALL.iter().position(|&j| j == shape.join),
+                        _ => None,
+                    };
+                    if let Some(position) = position {
+                        dropdown.set_selected(position as u32);
+                    }
+                } else if let Some(btn) = widget.downcast_ref::<ColorDialogButton>() {
+                    let color = shape.fill_color;
+                    btn.set_rgba(&RGBA::new(
+                        color.r as f32,
+                        color.g as f32,
+                        color.b as f32,
+                        color.a as f32,
+                    ));
+                } else if let Some(spin) = widget.downcast_ref::<SpinButton>() {
+                    spin.set_value(shape.corner_radius);
+                } else if let Some(scale) = widget.downcast_ref::<Scale>() {
+                    scale.set_value(shape.opacity);
+                }
+            }
+        });
+    }
+
+    /// Shows a color picked with the eyedropper on the color button and puts
+    /// its hex value on the clipboard.
+    fn connect_color_picked(toolbar: &Box, canvas: &EditorCanvas, toasts: &Toasts) {
+        let color_btn = Self::get_children(toolbar)
             .into_iter()
-            .filter_map(|w| w.downcast::<Button>().ok())
-            .collect();
+            .find_map(|w| w.downcast::<ColorDialogButton>().ok());
+        let toasts = toasts.clone();
 
-        for btn in buttons {
-            let canvas = canvas.clone();
+        canvas.connect_color_picked(move |canvas, hex| {
+            if let (Some(btn), Ok(rgba)) = (&color_btn, RGBA::parse(hex)) {
+                btn.set_rgba(&rgba);
+            }
+            WidgetExt::display(canvas).clipboard().set_text(hex);
+            toasts.show(&format!("Copied {}", hex), &[]);
+        });
+    }
 
-            btn.connect_clicked(move |button| {
-                if let Some(data) = canvas.get_image_data() {
-                    let result = match button.widget_name().as_str() {
-                        "btn_save" => Self::save_screenshot(&data),
-                        "btn_copy" => Self::copy_to_clipboard(&data),
-                        _ => Ok(()),
-                    };
+    fn recent_colors() -> Vec<RGBA> {
+        Config::load_recent_colors()
+            .iter()
+            .filter_map(|color| RGBA::parse(color).ok())
+            .collect()
+    }
 
-                    if let Err(e) = result {
-                        eprintln!("Error: {}", e);
-                    }
+    /// Sets the color and stroke width used last time, before the toolbar
+    /// remembers changes, so restoring doesn't count as using the color.
+    fn restore_tool_style(toolbar: &Box) {
+        let Some((color, stroke_width)) = Config::load_tool_style() else {
+            return;
+        };
+        for widget in Self::get_children(toolbar) {
+            if let Some(btn) = widget.downcast_ref::<ColorDialogButton>() {
+                if let Ok(rgba) = RGBA::parse(&color) {
+                    btn.set_rgba(&rgba);
                 }
-            });
+            } else if let Some(scale) = widget.downcast_ref::<Scale>() {
+                if scale.widget_name() == "stroke_width" {
+                    scale.set_value(stroke_width);
+                }
+            }
         }
     }
 
-    fn setup_keyboard_shortcuts(window: &ApplicationWindow, canvas: &EditorCanvas) {
-        let key_controller = EventControllerKey::new();
+    /// Saves the tool color and stroke width once they stop changing, and
+    /// keeps the recent colors up to date. Only the user's own changes
+    /// count, not the toolbar following the selection. Palette colors are
+    /// left out of the recent ones since they are always a click away.
+    fn connect_tool_style_memory(
+        toolbar: &Box,
+        swatches: &Rc<ColorSwatches>,
+        syncing: &Rc<Cell<bool>>,
+        toasts: &Toasts,
+    ) {
+        let children = Self::get_children(toolbar);
+        let Some(color_btn) = children
+            .iter()
+            .find_map(|w| w.clone().downcast::<ColorDialogButton>().ok())
+        else {
+            return;
+        };
+        let Some(stroke_scale) = children
+            .iter()
+            .filter_map(|w| w.clone().downcast::<Scale>().ok())
+            .find(|scale| scale.widget_name() == "stroke_width")
+        else {
+            return;
+        };
+
+        let write = {
+            let color_btn = color_btn.clone();
+            let stroke_scale = stroke_scale.clone();
+            let toasts = toasts.clone();
+            move || {
+                if let Err(e) =
+                    Config::save_tool_style(&color_btn.rgba().to_str(), stroke_scale.value())
+                {
+                    toasts.error("Could not save settings", &e);
+                }
+            }
+        };
+
+        let pending: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+        let schedule_save = {
+            let pending = pending.clone();
+            let write = write.clone();
+            move || {
+                if let Some(source) = pending.borrow_mut().take() {
+                    source.remove();
+                }
+                let done = pending.clone();
+                let write = write.clone();
+                let source = glib::timeout_add_local_once(TOOL_STYLE_SAVE_DELAY, move || {
+                    done.borrow_mut().take();
+                    write();
+                });
+                *pending.borrow_mut() = Some(source);
+            }
+        };
+
+        // A change still waiting when the window goes is saved right away.
+        toolbar.connect_unrealize(move |_| {
+            if let Some(source) = pending.borrow_mut().take() {
+                source.remove();
+                write();
+            }
+        });
 
-        let canvas = canvas.clone();
+        let swatches = swatches.clone();
+        let toasts = toasts.clone();
+        let color_syncing = syncing.clone();
+        let save_color = schedule_save.clone();
+        color_btn.connect_rgba_notify(move |button| {
+            if color_syncing.get() {
+                return;
+            }
+            let rgba = button.rgba();
+            if !swatches.in_palette(&rgba) {
+                if let Err(e) = Config::add_recent_color(&rgba.to_str()) {
+                    toasts.error("Could not save settings", &e);
+                }
+                swatches.set_recent(&Self::recent_colors());
+            }
+            save_color();
+        });
 
-        key_controller.connect_key_pressed(move |_, key, _, modifier| {
-            let ctrl = modifier.contains(gtk4::gdk::ModifierType::CONTROL_MASK);
+        let syncing = syncing.clone();
+        stroke_scale.connect_value_changed(move |_| {
+            if !syncing.get() {
+                schedule_save();
+            }
+        });
+    }
 
-            if ctrl {
-                match key {
-                    gtk4::gdk::Key::z => {
-                        canvas.undo();
-                        return glib::Propagation::Stop;
-                    }
-                    gtk4::gdk::Key::y => {
-                        canvas.redo();
-                        return glib::Propagation::Stop;
-                    }
-                    gtk4::gdk::Key::s => {
-                        if let Some(data) = canvas.get_image_data() {
-                            if let Err(e) = Self::save_screenshot(&data) {
-                                eprintln!("Save error: {}", e);
+    /// `set-color` sets the tool color from a CSS color string and
+    /// `palette-color` from the palette swatch with the given number.
+    fn install_color_actions(
+        window: &ApplicationWindow,
+        toolbar: &Box,
+        swatches: &Rc<ColorSwatches>,
+    ) {
+        let Some(color_btn) = Self::get_children(toolbar)
+            .into_iter()
+            .find_map(|w| w.downcast::<ColorDialogButton>().ok())
+        else {
+            return;
+        };
+
+        let set_color = gio::SimpleAction::new("set-color", Some(glib::VariantTy::STRING));
+        let btn = color_btn.clone();
+        set_color.connect_activate(move |_, value| {
+            if let Some(rgba) = value
+                .and_then(|v| v.str())
+                .and_then(|color| RGBA::parse(color).ok())
+            {
+                btn.set_rgba(&rgba);
+            }
+        });
+        window.add_action(&set_color);
+
+        let palette_color = gio::SimpleAction::new("palette-color", Some(glib::VariantTy::INT32));
+        let swatches = swatches.clone();
+        palette_color.connect_activate(move |_, value| {
+            if let Some(rgba) = value
+                .and_then(|v| v.get::<i32>())
+                .and_then(|number| swatches.palette_color(number as usize))
+            {
+                color_btn.set_rgba(&rgba);
+            }
+        });
+        window.add_action(&palette_color);
+    }
+
+    /// Controls inside the popover of the menu button named `menu_name`.
+    fn menu_controls(toolbar: &Box, menu_name: &str) -> Vec<gtk4::Widget> {
+        let Some(grid) = Self::get_children(toolbar)
+            .into_iter()
+            .filter_map(|w| w.downcast::<MenuButton>().ok())
+            .find(|menu| menu.widget_name() == menu_name)
+            .and_then(|menu| menu.popover())
+            .and_then(|popover| popover.child())
+            .and_then(|child| child.downcast::<Box>().ok())
+        else {
+            return Vec::new();
+        };
+
+        Self::get_children(&grid)
+            .into_iter()
+            .filter_map(|w| w.downcast::<Box>().ok())
+            .flat_map(|line| Self::get_children(&line))
+            .collect()
+    }
+
+    /// Hides the dash and cap controls while the marker is the tool, since
+    /// its strokes are always solid with flat ends.
+    fn connect_style_menu_to_tool(window: &ApplicationWindow, toolbar: &Box) {
+        let Some(tool) = window
+            .lookup_action("tool")
+            .and_then(|action| action.downcast::<gio::SimpleAction>().ok())
+        else {
+            return;
+        };
+        let controls: Vec<gtk4::Widget> = Self::menu_controls(toolbar, "style_menu")
+            .into_iter()
+            .filter_map(|widget| match widget.widget_name().as_str() {
+                // The dash dropdown is alone on its line, so the whole line goes.
+                "dash" => widget.parent(),
+                "line_cap" => Some(widget),
+                _ => None,
+            })
+            .collect();
+
+        tool.connect_state_notify(move |action| {
+            let marker = action
+                .state()
+                .and_then(|state| state.str().map(|name| name == "marker"))
+                .unwrap_or(false);
+            for widget in &controls {
+                widget.set_visible(!marker);
+            }
+        });
+    }
+
+    fn connect_style_menu(toolbar: &Box, canvas: &EditorCanvas) {
+        for widget in Self::menu_controls(toolbar, "style_menu") {
+            let canvas = canvas.clone();
+
+            if let Ok(dropdown) = widget.clone().downcast::<DropDown>() {
+                dropdown.connect_selected_notify(move |dropdown| {
+                    let idx = dropdown.selected() as usize;
+                    match dropdown.widget_name().as_str() {
+                        "fill_mode" => {
+                            if let Some(&fill) = FillMode::ALL.get(idx) {
+                                canvas.set_fill_mode(fill);
                             }
                         }
-                        return glib::Propagation::Stop;
-                    }
-                    gtk4::gdk::Key::c => {
-                        if let Some(data) = canvas.get_image_data() {
-                            if let Err(e) = Self::copy_to_clipboard(&data) {
-                                eprintln!("Copy error: {}", e);
+                        "dash" => {
+                            if let Some(&dash) = DashStyle::ALL.get(idx) {
+                                canvas.set_dash(dash);
                             }
                         }
-                        return glib::Propagation::Stop;
-                    }
-                    gtk4::gdk::Key::plus | gtk4::gdk::Key::equal => {
-                        canvas.zoom_in();
-                        return glib::Propagation::Stop;
-                    }
-                    gtk4::gdk::Key::minus => {
-                        canvas.zoom_out();
-                        return glib::Propagation::Stop;
-                    }
-                    gtk4::gdk::Key::_0 => {
-                        canvas.zoom_reset();
-                        return glib::Propagation::Stop;
+                        "line_cap" => {
+                            if let Some(&cap) = StrokeCap::ALL.get(idx) {
+                                canvas.set_line_cap(cap);
+                            }
+                        }
+                        "line_join" => {
+                            if let Some(&join) = StrokeJoin::ALL.get(idx) {
+                                canvas.set_line_join(join);
+                            }
+                        }
+                        _ => {}
                     }
-                    _ => {}
-                }
+                });
+            } else if let Ok(color_btn) = widget.clone().downcast::<ColorDialogButton>() {
+                color_btn.connect_rgba_notify(move |button| {
+                    let rgba = button.rgba();
+                    canvas.set_fill_color(Color::new(
+                        rgba.red() as f64,
+                        rgba.green() as f64,
+                        rgba.blue() as f64,
+                        rgba.alpha() as f64,
+                    ));
+                });
+            } else if let Ok(spin) = widget.clone().downcast::<SpinButton>() {
+                spin.connect_value_changed(move |spin| canvas.set_corner_radius(spin.value()));
+            } else if let Ok(scale) = widget.downcast::<Scale>() {
+                scale.connect_value_changed(move |scale| canvas.set_opacity(scale.value()));
             }
+        }
+    }
 
-            glib::Propagation::Proceed
-        });
+    fn insert_image_dialog(parent: Option<&gtk4::Window>, canvas: &EditorCanvas, toasts: &Toasts) {
+        let filter = gtk4::FileFilter::new();
+        filter.set_name(Some("Images"));
+        filter.add_pixbuf_formats();
 
-        window.add_controller(key_controller);
+        let dialog = FileDialog::builder()
+            .title("Insert Image")
+            .accept_label("_Insert")
+            .modal(true)
+            .default_filter(&filter)
+            .build();
+
+        let canvas = canvas.clone();
+        let toasts = toasts.clone();
+        dialog.open(parent, gio::Cancellable::NONE, move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            match gtk4::gdk_pixbuf::Pixbuf::from_file(path) {
+                Ok(pixbuf) => canvas.insert_image(pixbuf, None),
+                Err(e) => toasts.error("Could not insert image", &e),
+            }
+        });
     }
 
-    fn enable_action_buttons(toolbar: &Box, enabled: bool) {
-        for widget in Self::get_children(toolbar) {
-            if let Ok(btn) = widget.downcast::<Button>() {
-                let name = btn.widget_name();
-                if name == "btn_save" || name == "btn_copy" {
-                    btn.set_sensitive(enabled);
-                }
+    /// Save and copy need a screenshot to work on.
+    fn enable_image_actions(window: &ApplicationWindow, enabled: bool) {
+        for name in ["save", "save-as", "export", "copy-image"] {
+            if let Some(action) = window
+                .lookup_action(name)
+                .and_downcast::<gio::SimpleAction>()
+            {
+                action.set_enabled(enabled);
             }
         }
     }
@@ -491,48 +2029,4 @@ impl NiriShotApp {
 
         children
     }
-
-    fn save_screenshot(data: &[u8]) -> anyhow::Result<()> {
-        use chrono::Local;
-        use std::fs;
-        use std::path::PathBuf;
-
-        let pictures_dir = directories::UserDirs::new()
-            .and_then(|dirs| dirs.picture_dir().map(|p| p.to_path_buf()))
-            .unwrap_or_else(|| {
-                PathBuf::from(std::env::var("HOME").unwrap_or_default()).join("Pictures")
-            });
-
-        let screenshots_dir = pictures_dir.join("Screenshots");
-        fs::create_dir_all(&screenshots_dir)?;
-
-        let timestamp = Local::now().format("%Y-%m-%d-%H%M%S");
-        let filename = format!("screenshot-{}.png", timestamp);
-        let filepath = screenshots_dir.join(&filename);
-
-        fs::write(&filepath, data)?;
-        println!("Saved to: {}", filepath.display());
-
-        Ok(())
-    }
-
-    fn copy_to_clipboard(data: &[u8]) -> anyhow::Result<()> {
-        use std::io::Write;
-        use std::process::{Command, Stdio};
-
-        let mut child = Command::new("wl-copy")
-            .arg("--type")
-            .arg("image/png")
-            .stdin(Stdio::piped())
-            .spawn()?;
-
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin.write_all(data)?;
-        }
-
-        child.wait()?;
-        println!("Copied to clipboard");
-
-        Ok(())
-    }
 }
@@ -105,6 +105,30 @@ impl CaptureBackend {
         Self::capture_geometry(&geometry)
     }
 
+    /// Lets the user click a point on screen and returns the color of that
+    /// pixel as `#rrggbb`.
+    pub fn pick_color() -> Result<String> {
+        let slurp = Command::new("slurp").arg("-p").output()?;
+
+        if !slurp.status.success() {
+            return Err(anyhow!("Selection cancelled"));
+        }
+
+        let point = String::from_utf8_lossy(&slurp.stdout).trim().to_string();
+
+        if point.is_empty() {
+            return Err(anyhow!("No point selected"));
+        }
+
+        let data = Self::capture_geometry(&point)?;
+        let image = image::load_from_memory(&data)?.to_rgb8();
+        let pixel = image
+            .get_pixel_checked(0, 0)
+            .ok_or_else(|| anyhow!("Captured an empty image"))?;
+
+        Ok(format!("#{:02x}{:02x}{:02x}", pixel[0], pixel[1], pixel[2]))
+    }
+
     #[allow(dead_code)]
     pub fn is_available() -> bool {
         Command::new("which")
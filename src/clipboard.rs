@@ -0,0 +1,183 @@
+use gtk4::gdk;
+use gtk4::gdk::subclass::prelude::*;
+use gtk4::gio;
+use gtk4::glib;
+use gtk4::prelude::*;
+use std::cell::{OnceCell, RefCell};
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::pin::Pin;
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+
+use crate::config::ImageFormat;
+use crate::export::{self, ExportOptions};
+
+/// Puts a finished screenshot on the clipboard. With a display it is offered
+/// natively as PNG, JPEG and an HTML snippet, plus the file's URI, path and a
+/// Markdown link once it has been saved to `saved`. Without one it falls
+/// back to `wl-copy`.
+pub fn copy_image(data: &[u8], saved: Option<&Path>) -> anyhow::Result<()> {
+    let Some(display) = gdk::Display::default() else {
+        return copy_with_wl_copy(data);
+    };
+
+    display
+        .clipboard()
+        .set_content(Some(&content_provider(data, saved)))?;
+    Ok(())
+}
+
+fn content_provider(data: &[u8], saved: Option<&Path>) -> gdk::ContentProvider {
+    let png = glib::Bytes::from(data);
+    let mut providers = vec![
+        gdk::ContentProvider::for_bytes("image/png", &png),
+        EncodedImage::new(png).upcast(),
+    ];
+
+    if let Some(path) = saved {
+        let uri = gio::File::for_path(path).uri();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        providers.push(bytes_provider(
+            "text/uri-list",
+            format!("{}\r\n", uri).into_bytes(),
+        ));
+        providers.push(bytes_provider(
+            "text/markdown",
+            format!("![{}]({})", name, uri).into_bytes(),
+        ));
+        providers.push(bytes_provider(
+            "text/plain;charset=utf-8",
+            path.display().to_string().into_bytes(),
+        ));
+    }
+
+    gdk::ContentProvider::new_union(&providers)
+}
+
+fn bytes_provider(mime_type: &str, bytes: Vec<u8>) -> gdk::ContentProvider {
+    gdk::ContentProvider::for_bytes(mime_type, &glib::Bytes::from_owned(bytes))
+}
+
+const JPEG: &str = "image/jpeg";
+const HTML: &str = "text/html";
+
+/// Offers the screenshot as JPEG and as an HTML `<img>` with the PNG inlined.
+/// Both are large and slow to make for big captures, so they are only
+/// produced, off the main thread, when a program pastes them.
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct EncodedImage {
+        pub png: OnceCell<glib::Bytes>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for EncodedImage {
+        const NAME: &'static str = "NiriShotEncodedImage";
+        type Type = super::EncodedImage;
+        type ParentType = gdk::ContentProvider;
+    }
+
+    impl ObjectImpl for EncodedImage {}
+
+    impl ContentProviderImpl for EncodedImage {
+        fn formats(&self) -> gdk::ContentFormats {
+            gdk::ContentFormats::new(&[JPEG, HTML])
+        }
+
+        fn write_mime_type_future(
+            &self,
+            mime_type: &str,
+            stream: &gio::OutputStream,
+            io_priority: glib::Priority,
+        ) -> Pin<Box<dyn Future<Output = Result<(), glib::Error>> + 'static>> {
+            let png = self
+                .png
+                .get()
+                .cloned()
+                .unwrap_or_else(|| glib::Bytes::from_static(&[]));
+            let mime_type = mime_type.to_string();
+            let stream = stream.clone();
+
+            Box::pin(async move {
+                let data = gio::spawn_blocking(move || encode(&mime_type, &png))
+                    .await
+                    .map_err(|_| io_error("Encoding the image failed"))?
+                    .map_err(|e| io_error(&e.to_string()))?;
+                stream
+                    .write_all_future(data, io_priority)
+                    .await
+                    .map_err(|(_, e)| e)?;
+                Ok(())
+            })
+        }
+    }
+
+    fn encode(mime_type: &str, png: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match mime_type {
+            JPEG => export::encode(png, &ExportOptions::new(ImageFormat::Jpg)),
+            HTML => Ok(format!(
+                "<img src=\"data:image/png;base64,{}\">",
+                glib::base64_encode(png)
+            )
+            .into_bytes()),
+            _ => Err(anyhow::anyhow!("Unsupported format {}", mime_type)),
+        }
+    }
+
+    fn io_error(message: &str) -> glib::Error {
+        glib::Error::new(gio::IOErrorEnum::Failed, message)
+    }
+}
+
+glib::wrapper! {
+    pub struct EncodedImage(ObjectSubclass<imp::EncodedImage>)
+        @extends gdk::ContentProvider;
+}
+
+impl EncodedImage {
+    fn new(png: glib::Bytes) -> Self {
+        let provider: Self = glib::Object::new();
+        let _ = provider.imp().png.set(png);
+        provider
+    }
+}
+
+/// Copies PNG data with `wl-copy`, which keeps serving it after we exit.
+pub fn copy_with_wl_copy(data: &[u8]) -> anyhow::Result<()> {
+    let mut child = Command::new("wl-copy")
+        .arg("--type")
+        .arg("image/png")
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(data)?;
+    }
+
+    child.wait()?;
+    Ok(())
+}
+
+/// Clipboard content owned by this process disappears when it exits, so
+/// while we still own the clipboard the application is kept running in the
+/// background until another program takes it over.
+pub fn keep_alive(app: &gtk4::Application) {
+    let Some(display) = gdk::Display::default() else {
+        return;
+    };
+    let clipboard = display.clipboard();
+    if !clipboard.is_local() {
+        return;
+    }
+
+    let guard = Rc::new(RefCell::new(Some(app.hold())));
+    clipboard.connect_changed(move |clipboard| {
+        if !clipboard.is_local() {
+            guard.borrow_mut().take();
+        }
+    });
+}
@@ -0,0 +1,447 @@
+use std::collections::HashMap;
+
+/// An editor operation exposed as a window action.
+pub struct Command {
+    /// Name used in the `keybindings` section of `config.json`.
+    pub id: &'static str,
+    /// Detailed action name, e.g. `win.tool::rectangle`.
+    pub action: &'static str,
+    pub title: &'static str,
+    pub section: &'static str,
+    /// Default accelerators, in GTK accelerator syntax.
+    pub accels: &'static [&'static str],
+}
+
+const fn command(
+    id: &'static str,
+    action: &'static str,
+    title: &'static str,
+    section: &'static str,
+    accels: &'static [&'static str],
+) -> Command {
+    Command {
+        id,
+        action,
+        title,
+        section,
+        accels,
+    }
+}
+
+pub const COMMANDS: &[Command] = &[
+    command("tool-select", "win.tool::select", "Select", "Tools", &["v"]),
+    command(
+        "tool-rectangle",
+        "win.tool::rectangle",
+        "Rectangle",
+        "Tools",
+        &["r"],
+    ),
+    command("tool-circle", "win.tool::circle", "Circle", "Tools", &["e"]),
+    command("tool-line", "win.tool::line", "Line", "Tools", &["l"]),
+    command("tool-arrow", "win.tool::arrow", "Arrow", "Tools", &["a"]),
+    command(
+        "tool-freehand",
+        "win.tool::freehand",
+        "Freehand",
+        "Tools",
+        &["p"],
+    ),
+    command("tool-text", "win.tool::text", "Text", "Tools", &["t"]),
+    command("tool-blur", "win.tool::blur", "Blur", "Tools", &["b"]),
+    command(
+        "tool-highlight",
+        "win.tool::highlight",
+        "Highlight",
+        "Tools",
+        &["h"],
+    ),
+    command("tool-marker", "win.tool::marker", "Marker", "Tools", &["m"]),
+    command(
+        "tool-eyedropper",
+        "win.tool::eyedropper",
+        "Eyedropper",
+        "Tools",
+        &["i"],
+    ),
+    command(
+        "color-1",
+        "win.palette-color(1)",
+        "Palette Color 1",
+        "Colors",
+        &["1"],
+    ),
+    command(
+        "color-2",
+        "win.palette-color(2)",
+        "Palette Color 2",
+        "Colors",
+        &["2"],
+    ),
+    command(
+        "color-3",
+        "win.palette-color(3)",
+        "Palette Color 3",
+        "Colors",
+        &["3"],
+    ),
+    command(
+        "color-4",
+        "win.palette-color(4)",
+        "Palette Color 4",
+        "Colors",
+        &["4"],
+    ),
+    command(
+        "color-5",
+        "win.palette-color(5)",
+        "Palette Color 5",
+        "Colors",
+        &["5"],
+    ),
+    command(
+        "color-6",
+        "win.palette-color(6)",
+        "Palette Color 6",
+        "Colors",
+        &["6"],
+    ),
+    command(
+        "color-7",
+        "win.palette-color(7)",
+        "Palette Color 7",
+        "Colors",
+        &["7"],
+    ),
+    command(
+        "color-8",
+        "win.palette-color(8)",
+        "Palette Color 8",
+        "Colors",
+        &["8"],
+    ),
+    command(
+        "color-9",
+        "win.palette-color(9)",
+        "Palette Color 9",
+        "Colors",
+        &["9"],
+    ),
+    command("undo", "win.undo", "Undo", "Edit", &["<Control>z"]),
+    command(
+        "redo",
+        "win.redo",
+        "Redo",
+        "Edit",
+        &["<Control>y", "<Control><Shift>z"],
+    ),
+    command("cut", "win.cut", "Cut", "Edit", &["<Control>x"]),
+    command("copy", "win.copy", "Copy", "Edit", &["<Control>c"]),
+    command("paste", "win.paste", "Paste", "Edit", &["<Control>v"]),
+    command(
+        "duplicate",
+        "win.duplicate",
+        "Duplicate",
+        "Edit",
+        &["<Control>d"],
+    ),
+    command(
+        "select-all",
+        "win.select-all",
+        "Select All",
+        "Edit",
+        &["<Control>a"],
+    ),
+    command(
+        "delete",
+        "win.delete",
+        "Delete",
+        "Edit",
+        &["Delete", "BackSpace"],
+    ),
+    command("cancel", "win.cancel", "Cancel", "Edit", &["Escape"]),
+    command(
+        "insert-image",
+        "win.insert-image",
+        "Insert Image",
+        "Edit",
+        &["<Control>i"],
+    ),
+    command("group", "win.group", "Group", "Arrange", &["<Control>g"]),
+    command(
+        "ungroup",
+        "win.ungroup",
+        "Ungroup",
+        "Arrange",
+        &["<Control><Shift>g"],
+    ),
+    command(
+        "bring-forward",
+        "win.bring-forward",
+        "Bring Forward",
+        "Arrange",
+        &["<Control>bracketright"],
+    ),
+    command(
+        "send-backward",
+        "win.send-backward",
+        "Send Backward",
+        "Arrange",
+        &["<Control>bracketleft"],
+    ),
+    command(
+        "bring-to-front",
+        "win.bring-to-front",
+        "Bring to Front",
+        "Arrange",
+        &["<Control><Shift>bracketright"],
+    ),
+    command(
+        "send-to-back",
+        "win.send-to-back",
+        "Send to Back",
+        "Arrange",
+        &["<Control><Shift>bracketleft"],
+    ),
+    command("align-left", "win.align-left", "Align Left", "Arrange", &[]),
+    command(
+        "align-center",
+        "win.align-center",
+        "Align Center",
+        "Arrange",
+        &[],
+    ),
+    command(
+        "align-right",
+        "win.align-right",
+        "Align Right",
+        "Arrange",
+        &[],
+    ),
+    command("align-top", "win.align-top", "Align Top", "Arrange", &[]),
+    command(
+        "align-middle",
+        "win.align-middle",
+        "Align Middle",
+        "Arrange",
+        &[],
+    ),
+    command(
+        "align-bottom",
+        "win.align-bottom",
+        "Align Bottom",
+        "Arrange",
+        &[],
+    ),
+    command(
+        "distribute-horizontal",
+        "win.distribute-horizontal",
+        "Distribute Horizontally",
+        "Arrange",
+        &[],
+    ),
+    command(
+        "distribute-vertical",
+        "win.distribute-vertical",
+        "Distribute Vertically",
+        "Arrange",
+        &[],
+    ),
+    command(
+        "zoom-in",
+        "win.zoom-in",
+        "Zoom In",
+        "View",
+        &["<Control>plus", "<Control>equal"],
+    ),
+    command(
+        "zoom-out",
+        "win.zoom-out",
+        "Zoom Out",
+        "View",
+        &["<Control>minus"],
+    ),
+    command(
+        "zoom-reset",
+        "win.zoom-reset",
+        "Actual Size (1:1)",
+        "View",
+        &["<Control>0"],
+    ),
+    command(
+        "zoom-fit",
+        "win.zoom-fit",
+        "Fit to Window",
+        "View",
+        &["<Control>9"],
+    ),
+    command(
+        "zoom-fit-width",
+        "win.zoom-fit-width",
+        "Fit Width",
+        "View",
+        &[],
+    ),
+    command("zoom-25", "win.zoom-to(0.25)", "Zoom to 25%", "View", &[]),
+    command("zoom-50", "win.zoom-to(0.5)", "Zoom to 50%", "View", &[]),
+    command("zoom-100", "win.zoom-to(1.0)", "Zoom to 100%", "View", &[]),
+    command("zoom-200", "win.zoom-to(2.0)", "Zoom to 200%", "View", &[]),
+    command("zoom-400", "win.zoom-to(4.0)", "Zoom to 400%", "View", &[]),
+    command("zoom-800", "win.zoom-to(8.0)", "Zoom to 800%", "View", &[]),
+    command(
+        "toggle-pixel-grid",
+        "win.toggle-pixel-grid",
+        "Pixel Grid",
+        "View",
+        &["<Control>apostrophe"],
+    ),
+    command(
+        "toggle-minimap",
+        "win.toggle-minimap",
+        "Minimap",
+        "View",
+        &["<Control>m"],
+    ),
+    command(
+        "toggle-layers",
+        "win.toggle-layers",
+        "Layers Panel",
+        "View",
+        &["F9"],
+    ),
+    command(
+        "capture-fullscreen",
+        "win.capture::fullscreen",
+        "Capture Fullscreen",
+        "Capture",
+        &[],
+    ),
+    command(
+        "capture-region",
+        "win.capture::region",
+        "Capture Region",
+        "Capture",
+        &[],
+    ),
+    command(
+        "capture-window",
+        "win.capture::window",
+        "Capture Window",
+        "Capture",
+        &[],
+    ),
+    command("save", "win.save", "Save", "File", &["<Control>s"]),
+    command(
+        "save-as",
+        "win.save-as",
+        "Save As…",
+        "File",
+        &["<Control><Shift>s"],
+    ),
+    command("export-png", "win.export::png", "Save as PNG", "File", &[]),
+    command("export-jpg", "win.export::jpg", "Save as JPEG", "File", &[]),
+    command(
+        "copy-image",
+        "win.copy-image",
+        "Copy Image",
+        "File",
+        &["<Control><Shift>c"],
+    ),
+    command(
+        "show-shortcuts",
+        "win.show-shortcuts",
+        "Keyboard Shortcuts",
+        "Help",
+        &["F1", "<Control>question"],
+    ),
+    command(
+        "command-palette",
+        "win.command-palette",
+        "Command Palette",
+        "Help",
+        &["<Control><Shift>p", "slash"],
+    ),
+];
+
+/// Accelerators of `command`, taking overrides from the config into account.
+pub fn accels<'a>(
+    command: &'a Command,
+    overrides: &'a HashMap<String, Vec<String>>,
+) -> Vec<&'a str> {
+    match overrides.get(command.id) {
+        Some(accels) => accels.iter().map(String::as_str).collect(),
+        None => command.accels.to_vec(),
+    }
+}
+
+pub fn get(id: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|command| command.id == id)
+}
+
+/// Command bound to the action `name` with the given string target.
+pub fn find(name: &str, target: Option<&str>) -> Option<&'static Command> {
+    COMMANDS.iter().find(|command| {
+        let action = command
+            .action
+            .strip_prefix("win.")
+            .unwrap_or(command.action);
+        match action.split_once("::") {
+            Some((action, command_target)) => action == name && target == Some(command_target),
+            None => action == name && target.is_none(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accels_prefer_config_overrides() {
+        let rectangle = get("tool-rectangle").unwrap();
+        let mut overrides = HashMap::new();
+        assert_eq!(accels(rectangle, &overrides), vec!["r"]);
+
+        overrides.insert("tool-rectangle".to_string(), vec!["<Shift>r".to_string()]);
+        assert_eq!(accels(rectangle, &overrides), vec!["<Shift>r"]);
+
+        overrides.insert("tool-rectangle".to_string(), Vec::new());
+        assert!(accels(rectangle, &overrides).is_empty());
+    }
+
+    #[test]
+    fn overrides_of_other_commands_leave_defaults() {
+        let overrides = HashMap::from([("tool-circle".to_string(), vec!["c".to_string()])]);
+
+        assert_eq!(accels(get("tool-line").unwrap(), &overrides), vec!["l"]);
+    }
+
+    #[test]
+    fn find_matches_action_and_target() {
+        assert_eq!(
+            find("tool", Some("arrow")).map(|c| c.id),
+            Some("tool-arrow")
+        );
+        assert_eq!(find("undo", None).map(|c| c.id), Some("undo"));
+        assert!(find("tool", None).is_none());
+        assert!(find("undo", Some("x")).is_none());
+    }
+
+    #[test]
+    fn ids_and_default_accels_are_unique() {
+        for (idx, command) in COMMANDS.iter().enumerate() {
+            for other in &COMMANDS[idx + 1..] {
+                assert_ne!(command.id, other.id);
+                for accel in command.accels {
+                    assert!(
+                        !other.accels.contains(accel),
+                        "{} is bound to both {} and {}",
+                        accel,
+                        command.id,
+                        other.id
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,22 +1,72 @@
 #![allow(dead_code)]
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+const MAX_RECENT_FILES: usize = 10;
+const MAX_RECENT_COLORS: usize = 8;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub save_directory: PathBuf,
     pub filename_template: String,
     pub default_format: ImageFormat,
+    pub snap_grid_size: Option<f64>,
+    /// Outline each image pixel when zoomed in to 800% or more.
+    pub pixel_grid: bool,
+    /// Show a navigator for images larger than the window.
+    pub minimap: bool,
+    /// Tools, by command name without `tool-`, whose shapes snap to whole
+    /// image pixels.
+    pub pixel_snap_tools: Vec<String>,
+    /// Swatches next to the color button, as CSS colors. The first nine are
+    /// bound to the number keys.
+    pub palette: Vec<String>,
+    /// Folder the Save As dialog opens in, when asked to remember it.
+    pub save_as_directory: Option<PathBuf>,
+    /// Accelerators per command id, replacing the defaults.
+    pub keybindings: HashMap<String, Vec<String>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ImageFormat {
     Png,
     Jpg,
 }
 
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 2] = [ImageFormat::Png, ImageFormat::Jpg];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "PNG",
+            ImageFormat::Jpg => "JPEG",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpg => "jpg",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "jpg" | "jpeg" => Some(ImageFormat::Jpg),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(&path.extension()?.to_string_lossy())
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         let pictures_dir = directories::UserDirs::new()
@@ -29,6 +79,20 @@ impl Default for Config {
             save_directory: pictures_dir.join("Screenshots"),
             filename_template: "screenshot-%Y-%m-%d-%H%M%S".to_string(),
             default_format: ImageFormat::Png,
+            snap_grid_size: None,
+            pixel_grid: true,
+            minimap: true,
+            pixel_snap_tools: ["rectangle", "blur", "highlight"]
+                .map(String::from)
+                .to_vec(),
+            palette: [
+                "#ff0000", "#ff7800", "#f6d32d", "#2ec27e", "#1c71d8", "#9141ac", "#000000",
+                "#808080", "#ffffff",
+            ]
+            .map(String::from)
+            .to_vec(),
+            save_as_directory: None,
+            keybindings: HashMap::new(),
         }
     }
 }
@@ -57,7 +121,7 @@ impl Config {
             .map(|dirs| dirs.config_dir().join("config.json"))
     }
 
-    fn cache_dir() -> Option<PathBuf> {
+    pub fn cache_dir() -> Option<PathBuf> {
         directories::ProjectDirs::from("com", "github", "niri-shot")
             .map(|dirs| dirs.cache_dir().to_path_buf())
     }
@@ -71,6 +135,81 @@ impl Config {
         Ok(())
     }
 
+    /// Remembers `path` as the most recently saved file.
+    pub fn add_recent_file(path: &Path) -> anyhow::Result<()> {
+        let mut files = Self::load_recent_files();
+        files.retain(|file| file != path);
+        files.insert(0, path.to_path_buf());
+        files.truncate(MAX_RECENT_FILES);
+
+        if let Some(cache_dir) = Self::cache_dir() {
+            fs::create_dir_all(&cache_dir)?;
+            let content: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+            fs::write(cache_dir.join("recent-files"), content.join("\n"))?;
+        }
+        Ok(())
+    }
+
+    /// Recently saved files that still exist, newest first.
+    pub fn load_recent_files() -> Vec<PathBuf> {
+        Self::cache_dir()
+            .map(|dir| dir.join("recent-files"))
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| {
+                content
+                    .lines()
+                    .map(PathBuf::from)
+                    .filter(|path| path.exists())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Remembers `color` as the most recently used one.
+    pub fn add_recent_color(color: &str) -> anyhow::Result<()> {
+        let mut colors = Self::load_recent_colors();
+        colors.retain(|recent| recent != color);
+        colors.insert(0, color.to_string());
+        colors.truncate(MAX_RECENT_COLORS);
+
+        if let Some(cache_dir) = Self::cache_dir() {
+            fs::create_dir_all(&cache_dir)?;
+            fs::write(cache_dir.join("recent-colors"), colors.join("\n"))?;
+        }
+        Ok(())
+    }
+
+    /// Recently used colors, newest first.
+    pub fn load_recent_colors() -> Vec<String> {
+        Self::cache_dir()
+            .map(|dir| dir.join("recent-colors"))
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| content.lines().map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// Saves the tool color and stroke width, restored on the next launch.
+    pub fn save_tool_style(color: &str, stroke_width: f64) -> anyhow::Result<()> {
+        if let Some(cache_dir) = Self::cache_dir() {
+            fs::create_dir_all(&cache_dir)?;
+            fs::write(
+                cache_dir.join("tool-style"),
+                format!("{}\n{}", color, stroke_width),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn load_tool_style() -> Option<(String, f64)> {
+        let content = Self::cache_dir()
+            .map(|dir| dir.join("tool-style"))
+            .and_then(|path| fs::read_to_string(path).ok())?;
+        let mut lines = content.lines();
+        let color = lines.next()?.trim().to_string();
+        let stroke_width = lines.next()?.trim().parse().ok()?;
+        Some((color, stroke_width))
+    }
+
     pub fn load_last_region() -> Option<String> {
         Self::cache_dir()
             .map(|dir| dir.join("last-region"))
@@ -79,3 +218,22 @@ impl Config {
             .filter(|s| !s.is_empty())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_format_from_path_reads_the_extension() {
+        assert_eq!(
+            ImageFormat::from_path(Path::new("shot.PNG")),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("/tmp/shot.jpeg")),
+            Some(ImageFormat::Jpg)
+        );
+        assert_eq!(ImageFormat::from_path(Path::new("shot.webp")), None);
+        assert_eq!(ImageFormat::from_path(Path::new("shot")), None);
+    }
+}
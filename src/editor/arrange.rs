@@ -0,0 +1,291 @@
+use super::shapes::Shape;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    CenterHorizontal,
+    Right,
+    Top,
+    CenterVertical,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distribution {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZOrder {
+    Forward,
+    Backward,
+    Front,
+    Back,
+}
+
+/// Union of the bounds of the shapes at `indices`.
+pub fn selection_bounds(shapes: &[Shape], indices: &[usize]) -> Option<(f64, f64, f64, f64)> {
+    indices
+        .iter()
+        .filter_map(|&idx| shapes.get(idx))
+        .map(Shape::bounds)
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+}
+
+/// The indices that can be selected: in range, not locked and not hidden,
+/// sorted and without duplicates.
+pub fn selectable(shapes: &[Shape], mut indices: Vec<usize>) -> Vec<usize> {
+    indices.retain(|&idx| {
+        shapes
+            .get(idx)
+            .is_some_and(|shape| !shape.locked && !shape.hidden)
+    });
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+/// Aligns each shape (or whole group) at `indices` to the selection bounds.
+pub fn align(shapes: &mut [Shape], indices: &[usize], alignment: Alignment) {
+    let Some((min_x, min_y, max_x, max_y)) = selection_bounds(shapes, indices) else {
+        return;
+    };
+
+    for unit in units(shapes, indices) {
+        let Some((ux0, uy0, ux1, uy1)) = selection_bounds(shapes, &unit) else {
+            continue;
+        };
+
+        let (dx, dy) = match alignment {
+            Alignment::Left => (min_x - ux0, 0.0),
+            Alignment::CenterHorizontal => ((min_x + max_x - ux0 - ux1) / 2.0, 0.0),
+            Alignment::Right => (max_x - ux1, 0.0),
+            Alignment::Top => (0.0, min_y - uy0),
+            Alignment::CenterVertical => (0.0, (min_y + max_y - uy0 - uy1) / 2.0),
+            Alignment::Bottom => (0.0, max_y - uy1),
+        };
+
+        for idx in unit {
+            shapes[idx].translate(dx, dy);
+        }
+    }
+}
+
+/// Spaces shapes (or whole groups) at `indices` so the gaps between them are
+/// equal, keeping the outermost ones in place.
+pub fn distribute(shapes: &mut [Shape], indices: &[usize], distribution: Distribution) {
+    let mut units: Vec<_> = units(shapes, indices)
+        .into_iter()
+        .filter_map(|unit| selection_bounds(shapes, &unit).map(|bounds| (unit, bounds)))
+        .collect();
+
+    if units.len() < 3 {
+        return;
+    }
+
+    let span = |b: &(f64, f64, f64, f64)| match distribution {
+        Distribution::Horizontal => (b.0, b.2),
+        Distribution::Vertical => (b.1, b.3),
+    };
+
+    units.sort_by(|a, b| span(&a.1).0.total_cmp(&span(&b.1).0));
+
+    let start = span(&units[0].1).0;
+    let end = units
+        .iter()
+        .map(|(_, b)| span(b).1)
+        .fold(f64::MIN, f64::max);
+    let total: f64 = units.iter().map(|(_, b)| span(b).1 - span(b).0).sum();
+    let gap = (end - start - total) / (units.len() - 1) as f64;
+
+    let mut cursor = start;
+    for (unit, bounds) in units {
+        let (lo, hi) = span(&bounds);
+        let delta = cursor - lo;
+        for idx in unit {
+            match distribution {
+                Distribution::Horizontal => shapes[idx].translate(delta, 0.0),
+                Distribution::Vertical => shapes[idx].translate(0.0, delta),
+            }
+        }
+        cursor += hi - lo + gap;
+    }
+}
+
+/// Changes the stacking order of the shapes at `indices` (sorted ascending)
+/// and returns their new indices.
+pub fn reorder(shapes: &mut Vec<Shape>, indices: &[usize], order: ZOrder) -> Vec<usize> {
+    let mut selected: Vec<usize> = indices
+        .iter()
+        .copied()
+        .filter(|&i| i < shapes.len())
+        .collect();
+
+    match order {
+        ZOrder::Forward => {
+            for pos in (0..selected.len()).rev() {
+                let idx = selected[pos];
+                let blocked = selected.get(pos + 1) == Some(&(idx + 1));
+                if idx + 1 < shapes.len() && !blocked {
+                    shapes.swap(idx, idx + 1);
+                    selected[pos] += 1;
+                }
+            }
+        }
+        ZOrder::Backward => {
+            for pos in 0..selected.len() {
+                let idx = selected[pos];
+                let blocked = pos > 0 && selected[pos - 1] + 1 == idx;
+                if idx > 0 && !blocked {
+                    shapes.swap(idx, idx - 1);
+                    selected[pos] -= 1;
+                }
+            }
+        }
+        ZOrder::Front | ZOrder::Back => {
+            let mut moved = Vec::with_capacity(selected.len());
+            for &idx in selected.iter().rev() {
+                moved.push(shapes.remove(idx));
+            }
+            moved.reverse();
+
+            let count = moved.len();
+            if order == ZOrder::Front {
+                let start = shapes.len();
+                shapes.extend(moved);
+                selected = (start..start + count).collect();
+            } else {
+                shapes.splice(0..0, moved);
+                selected = (0..count).collect();
+            }
+        }
+    }
+
+    selected
+}
+
+/// Splits `indices` into units that move together: one per group, and one
+/// per ungrouped shape.
+fn units(shapes: &[Shape], indices: &[usize]) -> Vec<Vec<usize>> {
+    let mut units: Vec<Vec<usize>> = Vec::new();
+    let mut groups: Vec<(u32, usize)> = Vec::new();
+
+    for &idx in indices {
+        match shapes.get(idx).and_then(|shape| shape.group) {
+            Some(group) => match groups.iter().find(|(id, _)| *id == group) {
+                Some(&(_, unit)) => units[unit].push(idx),
+                None => {
+                    groups.push((group, units.len()));
+                    units.push(vec![idx]);
+                }
+            },
+            None if idx < shapes.len() => units.push(vec![idx]),
+            None => {}
+        }
+    }
+
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::shapes::ShapeType;
+
+    fn rectangle(x: f64, y: f64, width: f64, height: f64) -> Shape {
+        Shape {
+            shape_type: ShapeType::Rectangle,
+            start_x: x,
+            start_y: y,
+            end_x: x + width,
+            end_y: y + height,
+            ..Default::default()
+        }
+    }
+
+    fn lefts(shapes: &[Shape]) -> Vec<f64> {
+        shapes.iter().map(|shape| shape.bounds().0).collect()
+    }
+
+    #[test]
+    fn locked_and_hidden_shapes_are_not_selectable() {
+        let mut shapes = vec![rectangle(0.0, 0.0, 1.0, 1.0); 4];
+        shapes[1].locked = true;
+        shapes[2].hidden = true;
+
+        assert_eq!(selectable(&shapes, vec![3, 2, 1, 0, 3, 9]), vec![0, 3]);
+        assert!(selectable(&shapes, vec![1, 2]).is_empty());
+    }
+
+    #[test]
+    fn distribute_evens_out_the_gaps() {
+        let mut shapes = vec![
+            rectangle(0.0, 0.0, 10.0, 10.0),
+            rectangle(90.0, 0.0, 10.0, 10.0),
+            rectangle(15.0, 0.0, 30.0, 10.0),
+        ];
+
+        distribute(&mut shapes, &[0, 1, 2], Distribution::Horizontal);
+
+        assert_eq!(lefts(&shapes), vec![0.0, 90.0, 35.0]);
+    }
+
+    #[test]
+    fn distribute_moves_groups_as_one() {
+        let mut shapes = vec![
+            rectangle(0.0, 0.0, 10.0, 10.0),
+            rectangle(0.0, 20.0, 10.0, 10.0),
+            rectangle(0.0, 30.0, 10.0, 20.0),
+            rectangle(0.0, 90.0, 10.0, 10.0),
+        ];
+        shapes[1].group = Some(0);
+        shapes[2].group = Some(0);
+
+        distribute(&mut shapes, &[0, 1, 2, 3], Distribution::Vertical);
+
+        let tops: Vec<f64> = shapes.iter().map(|shape| shape.bounds().1).collect();
+        assert_eq!(tops, vec![0.0, 35.0, 45.0, 90.0]);
+    }
+
+    #[test]
+    fn distribute_needs_three_units() {
+        let mut shapes = vec![
+            rectangle(0.0, 0.0, 10.0, 10.0),
+            rectangle(20.0, 0.0, 10.0, 10.0),
+            rectangle(90.0, 0.0, 10.0, 10.0),
+        ];
+        shapes[0].group = Some(3);
+        shapes[1].group = Some(3);
+
+        distribute(&mut shapes, &[0, 1, 2], Distribution::Horizontal);
+
+        assert_eq!(lefts(&shapes), vec![0.0, 20.0, 90.0]);
+    }
+
+    #[test]
+    fn align_uses_the_selection_bounds() {
+        let mut shapes = vec![
+            rectangle(10.0, 0.0, 10.0, 10.0),
+            rectangle(40.0, 0.0, 20.0, 10.0),
+        ];
+
+        align(&mut shapes, &[0, 1], Alignment::Right);
+        assert_eq!(lefts(&shapes), vec![50.0, 40.0]);
+
+        align(&mut shapes, &[0, 1], Alignment::CenterHorizontal);
+        assert_eq!(lefts(&shapes), vec![45.0, 40.0]);
+    }
+
+    #[test]
+    fn reorder_keeps_adjacent_selections_together() {
+        let mut shapes: Vec<Shape> = (0..4).map(|i| rectangle(i as f64, 0.0, 1.0, 1.0)).collect();
+
+        assert_eq!(reorder(&mut shapes, &[2, 3], ZOrder::Forward), vec![2, 3]);
+        assert_eq!(reorder(&mut shapes, &[1, 2], ZOrder::Backward), vec![0, 1]);
+        assert_eq!(lefts(&shapes), vec![1.0, 2.0, 0.0, 3.0]);
+
+        assert_eq!(reorder(&mut shapes, &[0, 2], ZOrder::Front), vec![2, 3]);
+        assert_eq!(lefts(&shapes), vec![2.0, 3.0, 1.0, 0.0]);
+    }
+}
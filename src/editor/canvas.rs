@@ -1,13 +1,55 @@
+use gtk4::gdk::ModifierType;
 use gtk4::gdk_pixbuf::Pixbuf;
 use gtk4::gio::{Cancellable, MemoryInputStream};
 use gtk4::prelude::*;
 use gtk4::subclass::prelude::*;
-use gtk4::{glib, DrawingArea, EventControllerMotion, GestureClick, GestureDrag};
+use gtk4::{gdk, glib, DrawingArea, DropTarget, EventControllerMotion, GestureClick, GestureDrag};
 use std::cell::{Cell, RefCell};
 use std::f64::consts::PI;
-
-use super::shapes::{Shape, ShapeType};
-use super::tools::{Tool, ToolType};
+use std::sync::OnceLock;
+
+use super::arrange::{self, Alignment, Distribution, ZOrder};
+use super::clipboard;
+use super::constraints::{constrain_aspect, constrain_square, snap_angle, snap_direction};
+use super::history::History;
+use super::render::{self, RenderCache};
+use super::shapes::{Color, DashStyle, FillMode, Shape, ShapeType, StrokeCap, StrokeJoin};
+use super::smoothing::{simplify_epsilon, StrokeSmoother};
+use super::snapping::{SnapGuide, SnapTargets, SNAP_DISTANCE};
+use super::tools::{shape_stroke_width, Tool, ToolType};
+
+/// How far duplicated shapes, and shapes pasted away from the pointer, are
+/// moved from the originals.
+const PASTE_OFFSET: f64 = 10.0;
+
+/// Arrow keys move the selection by one pixel, or by this much with Shift.
+const NUDGE_LARGE: f64 = 10.0;
+
+const MIN_ZOOM: f64 = 0.1;
+const MAX_ZOOM: f64 = 32.0;
+
+/// Zoom from which the pixel grid is drawn.
+const PIXEL_GRID_ZOOM: f64 = 8.0;
+
+/// Zoom factor of one zoom in/out step or one scroll wheel notch.
+const ZOOM_STEP: f64 = 1.25;
+
+/// Image pixels across the eyedropper's magnified preview, and the size each
+/// is drawn at.
+const LOUPE_PIXELS: i32 = 11;
+const LOUPE_SCALE: f64 = 10.0;
+/// Distance from the pointer to the preview, in widget pixels.
+const LOUPE_OFFSET: f64 = 24.0;
+
+/// How the zoom follows the size of the visible area.
+#[derive(Clone, Copy, PartialEq)]
+pub enum FitMode {
+    /// The whole image, enlarging small ones.
+    Window,
+    Width,
+    /// The whole image, but never above 100%. Used for newly opened images.
+    Shrink,
+}
 
 #[derive(Clone, Copy, PartialEq, Default)]
 pub enum HandleType {
@@ -25,12 +67,22 @@ mod imp {
 
     pub struct EditorCanvas {
         pub image: RefCell<Option<Pixbuf>>,
+        pub render_cache: RefCell<RenderCache>,
+        /// Bumped whenever the shapes change, so the render cache knows
+        /// when its layer is stale.
+        pub generation: Cell<u64>,
         pub shapes: RefCell<Vec<Shape>>,
-        pub redo_stack: RefCell<Vec<Shape>>,
+        pub history: RefCell<History>,
+        /// Set when the shapes change, cleared when the image is saved.
+        pub dirty: Cell<bool>,
+        pub drag_snapshot: RefCell<Option<Vec<Shape>>>,
         pub current_shape: RefCell<Option<Shape>>,
+        pub smoother: RefCell<StrokeSmoother>,
+        pub straight_stroke: Cell<bool>,
         pub tool: RefCell<Tool>,
         pub drawing: Cell<bool>,
-        pub selected_index: Cell<Option<usize>>,
+        pub selection: RefCell<Vec<usize>>,
+        pub rubber_band: Cell<Option<(f64, f64, f64, f64)>>,
         pub dragging: Cell<bool>,
         pub drag_start_x: Cell<f64>,
         pub drag_start_y: Cell<f64>,
@@ -41,8 +93,25 @@ mod imp {
         pub text_input_buffer: RefCell<String>,
         pub active_handle: Cell<HandleType>,
         pub initial_rotation: Cell<f64>,
+        pub draw_origin: Cell<(f64, f64)>,
+        pub resize_anchor: Cell<(f64, f64)>,
+        pub resize_aspect: Cell<f64>,
+        pub snap_guides: RefCell<Vec<SnapGuide>>,
+        pub grid_size: Cell<Option<f64>>,
+        pub pixel_grid: Cell<bool>,
+        /// Tools whose shapes start and end on whole image pixels.
+        pub pixel_snap: RefCell<Vec<ToolType>>,
+        pub pointer: Cell<Option<(f64, f64)>>,
+        /// Alt is held, turning drawing tools into the eyedropper.
+        pub eyedropper_held: Cell<bool>,
+        pub paste_count: Cell<u32>,
         pub zoom: Cell<f64>,
+        /// Fit kept up as the window resizes, until the user zooms.
+        pub fit: Cell<Option<FitMode>>,
+        /// Zoom when the current pinch began.
+        pub pinch_zoom: Cell<f64>,
         pub panning: Cell<bool>,
+        /// Scroll position when the pan drag began.
         pub pan_start_x: Cell<f64>,
         pub pan_start_y: Cell<f64>,
     }
@@ -51,12 +120,19 @@ mod imp {
         fn default() -> Self {
             Self {
                 image: RefCell::new(None),
+                render_cache: RefCell::new(RenderCache::default()),
+                generation: Cell::new(0),
                 shapes: RefCell::new(Vec::new()),
-                redo_stack: RefCell::new(Vec::new()),
+                history: RefCell::new(History::default()),
+                dirty: Cell::new(false),
+                drag_snapshot: RefCell::new(None),
                 current_shape: RefCell::new(None),
+                smoother: RefCell::new(StrokeSmoother::default()),
+                straight_stroke: Cell::new(false),
                 tool: RefCell::new(Tool::default()),
                 drawing: Cell::new(false),
-                selected_index: Cell::new(None),
+                selection: RefCell::new(Vec::new()),
+                rubber_band: Cell::new(None),
                 dragging: Cell::new(false),
                 drag_start_x: Cell::new(0.0),
                 drag_start_y: Cell::new(0.0),
@@ -67,7 +143,19 @@ mod imp {
                 text_input_buffer: RefCell::new(String::new()),
                 active_handle: Cell::new(HandleType::None),
                 initial_rotation: Cell::new(0.0),
+                draw_origin: Cell::new((0.0, 0.0)),
+                resize_anchor: Cell::new((0.0, 0.0)),
+                resize_aspect: Cell::new(1.0),
+                snap_guides: RefCell::new(Vec::new()),
+                grid_size: Cell::new(None),
+                pixel_grid: Cell::new(false),
+                pixel_snap: RefCell::new(Vec::new()),
+                pointer: Cell::new(None),
+                eyedropper_held: Cell::new(false),
+                paste_count: Cell::new(0),
                 zoom: Cell::new(1.0),
+                fit: Cell::new(None),
+                pinch_zoom: Cell::new(1.0),
                 panning: Cell::new(false),
                 pan_start_x: Cell::new(0.0),
                 pan_start_y: Cell::new(0.0),
@@ -88,6 +176,20 @@ mod imp {
             self.obj().setup_drawing();
             self.obj().setup_events();
         }
+
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<glib::subclass::Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    glib::subclass::Signal::builder("shapes-changed").build(),
+                    glib::subclass::Signal::builder("selection-changed").build(),
+                    glib::subclass::Signal::builder("view-changed").build(),
+                    glib::subclass::Signal::builder("color-picked")
+                        .param_types([String::static_type()])
+                        .build(),
+                ]
+            })
+        }
     }
 
     impl WidgetImpl for EditorCanvas {}
@@ -126,14 +228,14 @@ impl EditorCanvas {
         click.set_button(1);
 
         let canvas = self.clone();
-        click.connect_pressed(move |_, _, x, y| {
+        click.connect_pressed(move |gesture, _, x, y| {
             canvas.grab_focus();
-            canvas.on_press(x, y);
+            canvas.on_press(x, y, gesture.current_event_state());
         });
 
         let canvas = self.clone();
-        click.connect_released(move |_, _, x, y| {
-            canvas.on_release(x, y);
+        click.connect_released(move |gesture, _, x, y| {
+            canvas.on_release(x, y, gesture.current_event_state());
         });
 
         self.add_controller(click);
@@ -146,13 +248,13 @@ impl EditorCanvas {
         });
 
         let canvas = self.clone();
-        drag.connect_drag_update(move |_, offset_x, offset_y| {
-            canvas.on_drag_update(offset_x, offset_y);
+        drag.connect_drag_update(move |gesture, offset_x, offset_y| {
+            canvas.on_drag_update(offset_x, offset_y, gesture.current_event_state());
         });
 
         let canvas = self.clone();
-        drag.connect_drag_end(move |_, offset_x, offset_y| {
-            canvas.on_drag_end(offset_x, offset_y);
+        drag.connect_drag_end(move |gesture, offset_x, offset_y| {
+            canvas.on_drag_end(offset_x, offset_y, gesture.current_event_state());
         });
 
         self.add_controller(drag);
@@ -160,15 +262,30 @@ impl EditorCanvas {
         let motion = EventControllerMotion::new();
 
         let canvas = self.clone();
-        motion.connect_motion(move |_, x, y| {
-            canvas.on_motion(x, y);
+        motion.connect_motion(move |controller, x, y| {
+            canvas.on_motion(x, y, controller.current_event_state());
+        });
+
+        let canvas = self.clone();
+        motion.connect_leave(move |_| {
+            canvas.imp().pointer.set(None);
+            canvas.emit_view_changed();
+            if canvas.eyedropper_active() {
+                canvas.queue_draw();
+            }
         });
 
         self.add_controller(motion);
 
         let key = gtk4::EventControllerKey::new();
         let canvas = self.clone();
-        key.connect_key_pressed(move |_, keyval, _, _| canvas.on_key_pressed(keyval));
+        key.connect_key_pressed(move |_, keyval, _, state| canvas.on_key_pressed(keyval, state));
+
+        let canvas = self.clone();
+        key.connect_modifiers(move |_, state| {
+            canvas.set_eyedropper_held(state.contains(ModifierType::ALT_MASK));
+            glib::Propagation::Proceed
+        });
 
         self.add_controller(key);
 
@@ -177,10 +294,15 @@ impl EditorCanvas {
         scroll.connect_scroll(move |controller, _dx, dy| {
             let state = controller.current_event_state();
             if state.contains(gtk4::gdk::ModifierType::CONTROL_MASK) {
-                if dy < 0.0 {
-                    canvas.zoom_in();
-                } else if dy > 0.0 {
-                    canvas.zoom_out();
+                // Touchpads scroll in fractions of a notch, which zoom
+                // smoothly by the matching fraction of a step.
+                let zoom = canvas.get_zoom() * ZOOM_STEP.powf(-dy);
+                match canvas.imp().pointer.get() {
+                    Some((x, y)) => {
+                        let scale = canvas.get_zoom();
+                        canvas.zoom_at(zoom, x * scale, y * scale);
+                    }
+                    None => canvas.set_zoom(zoom),
                 }
                 return glib::Propagation::Stop;
             }
@@ -189,74 +311,93 @@ impl EditorCanvas {
 
         self.add_controller(scroll);
 
-        let middle_click = GestureClick::new();
-        middle_click.set_button(2);
-
-        let canvas = self.clone();
-        middle_click.connect_pressed(move |_, _, x, y| {
-            canvas.imp().panning.set(true);
-            canvas.imp().pan_start_x.set(x);
-            canvas.imp().pan_start_y.set(y);
-        });
+        let pinch = gtk4::GestureZoom::new();
 
         let canvas = self.clone();
-        middle_click.connect_released(move |_, _, _, _| {
-            canvas.imp().panning.set(false);
+        pinch.connect_begin(move |_, _| {
+            canvas.imp().pinch_zoom.set(canvas.get_zoom());
         });
 
-        self.add_controller(middle_click);
-
-        let pan_drag = GestureDrag::new();
-        pan_drag.set_button(2);
-
         let canvas = self.clone();
-        pan_drag.connect_drag_update(move |_, offset_x, offset_y| {
-            if canvas.imp().panning.get() {
-                canvas.pan(-offset_x, -offset_y);
+        pinch.connect_scale_changed(move |gesture, scale| {
+            if let Some((x, y)) = gesture.bounding_box_center() {
+                canvas.zoom_at(canvas.imp().pinch_zoom.get() * scale, x, y);
             }
         });
 
-        self.add_controller(pan_drag);
+        self.add_controller(pinch);
 
-        let right_click = GestureClick::new();
-        right_click.set_button(3);
-
-        let canvas = self.clone();
-        right_click.connect_pressed(move |gesture, _, x, y| {
-            let state = gesture.current_event_state();
-            if state.contains(gtk4::gdk::ModifierType::SHIFT_MASK) {
-                canvas.imp().panning.set(true);
-                canvas.imp().pan_start_x.set(x);
-                canvas.imp().pan_start_y.set(y);
+        // Allocation can't change the zoom directly, so refitting waits for
+        // the resize to finish.
+        self.connect_resize(|canvas, _, _| {
+            if canvas.imp().fit.get().is_some() {
+                let canvas = canvas.downgrade();
+                glib::idle_add_local_once(move || {
+                    if let Some(canvas) = canvas.upgrade() {
+                        canvas.apply_fit();
+                    }
+                });
             }
         });
 
-        let canvas = self.clone();
-        right_click.connect_released(move |_, _, _, _| {
-            canvas.imp().panning.set(false);
-        });
+        // Middle-drag, or Shift+right-drag, scrolls the view.
+        for button in [2, 3] {
+            let pan_drag = GestureDrag::new();
+            pan_drag.set_button(button);
+
+            let canvas = self.clone();
+            pan_drag.connect_drag_begin(move |gesture, _, _| {
+                let shift = gesture
+                    .current_event_state()
+                    .contains(ModifierType::SHIFT_MASK);
+                if button == 2 || shift {
+                    canvas.begin_pan();
+                }
+            });
 
-        self.add_controller(right_click);
+            let canvas = self.clone();
+            pan_drag.connect_drag_update(move |_, offset_x, offset_y| {
+                if canvas.imp().panning.get() {
+                    canvas.pan_drag(offset_x, offset_y);
+                }
+            });
+
+            let canvas = self.clone();
+            pan_drag.connect_drag_end(move |_, _, _| {
+                canvas.imp().panning.set(false);
+            });
 
-        let right_pan_drag = GestureDrag::new();
-        right_pan_drag.set_button(3);
+            self.add_controller(pan_drag);
+        }
 
+        let drop = DropTarget::new(gtk4::gio::File::static_type(), gdk::DragAction::COPY);
         let canvas = self.clone();
-        right_pan_drag.connect_drag_update(move |gesture, offset_x, offset_y| {
-            let state = gesture.current_event_state();
-            if state.contains(gtk4::gdk::ModifierType::SHIFT_MASK) && canvas.imp().panning.get() {
-                canvas.pan(-offset_x, -offset_y);
+        drop.connect_drop(move |_, value, x, y| {
+            let Some(path) = value
+                .get::<gtk4::gio::File>()
+                .ok()
+                .and_then(|file| file.path())
+            else {
+                return false;
+            };
+
+            match Pixbuf::from_file(&path) {
+                Ok(pixbuf) => {
+                    canvas.insert_image(pixbuf, Some(canvas.screen_to_canvas(x, y)));
+                    true
+                }
+                Err(_) => false,
             }
         });
 
-        self.add_controller(right_pan_drag);
+        self.add_controller(drop);
     }
 
-    fn on_key_pressed(&self, keyval: gtk4::gdk::Key) -> glib::Propagation {
+    fn on_key_pressed(&self, keyval: gtk4::gdk::Key, state: ModifierType) -> glib::Propagation {
         let imp = self.imp();
 
         if !imp.text_input_active.get() {
-            return glib::Propagation::Proceed;
+            return self.on_nudge_key(keyval, state);
         }
 
         match keyval {
@@ -286,8 +427,32 @@ impl EditorCanvas {
         }
     }
 
-    fn on_press(&self, screen_x: f64, screen_y: f64) {
+    fn on_nudge_key(&self, keyval: gtk4::gdk::Key, state: ModifierType) -> glib::Propagation {
+        let step = if state.contains(ModifierType::SHIFT_MASK) {
+            NUDGE_LARGE
+        } else {
+            1.0
+        };
+
+        let (dx, dy) = match keyval {
+            gtk4::gdk::Key::Left => (-step, 0.0),
+            gtk4::gdk::Key::Right => (step, 0.0),
+            gtk4::gdk::Key::Up => (0.0, -step),
+            gtk4::gdk::Key::Down => (0.0, step),
+            _ => return glib::Propagation::Proceed,
+        };
+
+        if self.imp().selection.borrow().is_empty() {
+            return glib::Propagation::Proceed;
+        }
+
+        self.edit_selected("nudge", |shape| shape.translate(dx, dy));
+        glib::Propagation::Stop
+    }
+
+    fn on_press(&self, screen_x: f64, screen_y: f64, state: ModifierType) {
         let (x, y) = self.screen_to_canvas(screen_x, screen_y);
+        let (x, y) = self.snap_to_pixel(x, y);
         let imp = self.imp();
         let tool_type = imp.tool.borrow().tool_type;
 
@@ -296,6 +461,13 @@ impl EditorCanvas {
             return;
         }
 
+        self.set_eyedropper_held(state.contains(ModifierType::ALT_MASK));
+        if self.eyedropper_active() {
+            let (x, y) = self.screen_to_canvas(screen_x, screen_y);
+            self.pick_color(x, y);
+            return;
+        }
+
         match tool_type {
             ToolType::Select => {
                 let handle = self.hit_test_handle(x, y);
@@ -305,25 +477,46 @@ impl EditorCanvas {
                     imp.drag_start_x.set(x);
                     imp.drag_start_y.set(y);
 
-                    if handle == HandleType::Rotation {
-                        if let Some(idx) = imp.selected_index.get() {
-                            let shapes = imp.shapes.borrow();
-                            if let Some(shape) = shapes.get(idx) {
+                    if let Some(idx) = self.single_selection() {
+                        let shapes = imp.shapes.borrow();
+                        if let Some(shape) = shapes.get(idx) {
+                            if handle == HandleType::Rotation {
                                 imp.initial_rotation.set(shape.rotation);
+                            } else {
+                                self.store_resize_anchor(shape, handle);
                             }
                         }
                     }
                 } else if let Some(idx) = self.hit_test(x, y) {
-                    imp.selected_index.set(Some(idx));
+                    let members = self.group_members(idx);
                     imp.active_handle.set(HandleType::None);
-                    imp.dragging.set(true);
-                    imp.drag_start_x.set(x);
-                    imp.drag_start_y.set(y);
+
+                    if state.contains(ModifierType::SHIFT_MASK) {
+                        let mut selection = imp.selection.borrow_mut();
+                        if selection.contains(&idx) {
+                            selection.retain(|i| !members.contains(i));
+                        } else {
+                            selection.extend(members);
+                            selection.sort_unstable();
+                            selection.dedup();
+                        }
+                    } else {
+                        if !imp.selection.borrow().contains(&idx) {
+                            *imp.selection.borrow_mut() = members;
+                        }
+                        imp.dragging.set(true);
+                        imp.drag_start_x.set(x);
+                        imp.drag_start_y.set(y);
+                    }
                 } else {
-                    imp.selected_index.set(None);
+                    if !state.contains(ModifierType::SHIFT_MASK) {
+                        imp.selection.borrow_mut().clear();
+                    }
                     imp.active_handle.set(HandleType::None);
+                    imp.rubber_band.set(Some((x, y, x, y)));
                 }
                 self.queue_draw();
+                self.emit_selection_changed();
             }
             ToolType::Text => {
                 imp.text_input_active.set(true);
@@ -331,27 +524,16 @@ impl EditorCanvas {
                 *imp.text_input_buffer.borrow_mut() = String::new();
                 self.queue_draw();
             }
-            ToolType::FreeHand => {
+            ToolType::FreeHand | ToolType::Marker => {
                 imp.drawing.set(true);
-                imp.redo_stack.borrow_mut().clear();
+                imp.straight_stroke.set(false);
                 let tool = imp.tool.borrow();
-                let shape = Shape {
-                    shape_type: ShapeType::FreeHand,
-                    start_x: x,
-                    start_y: y,
-                    end_x: x,
-                    end_y: y,
-                    color: tool.color,
-                    stroke_width: tool.stroke_width,
-                    filled: false,
-                    points: vec![(x, y)],
-                    ..Default::default()
-                };
-                *imp.current_shape.borrow_mut() = Some(shape);
+                imp.smoother.borrow_mut().reset(tool.smoothing, x, y);
+                *imp.current_shape.borrow_mut() = tool.create_stroke_shape(x, y);
             }
             _ => {
                 imp.drawing.set(true);
-                imp.redo_stack.borrow_mut().clear();
+                imp.draw_origin.set((x, y));
                 let tool = imp.tool.borrow();
                 if let Some(shape) = tool.create_shape(x, y, x, y) {
                     *imp.current_shape.borrow_mut() = Some(shape);
@@ -360,81 +542,205 @@ impl EditorCanvas {
         }
     }
 
-    fn on_motion(&self, screen_x: f64, screen_y: f64) {
+    fn on_motion(&self, screen_x: f64, screen_y: f64, state: ModifierType) {
         let (x, y) = self.screen_to_canvas(screen_x, screen_y);
         let imp = self.imp();
+        imp.pointer.set(Some((x, y)));
+        self.emit_view_changed();
+
+        self.set_eyedropper_held(state.contains(ModifierType::ALT_MASK));
+        if self.eyedropper_active() {
+            self.queue_draw();
+        }
 
         if !imp.drawing.get() {
             return;
         }
 
+        let (x, y) = self.snap_to_pixel(x, y);
         let tool_type = imp.tool.borrow().tool_type;
+        let shift = state.contains(ModifierType::SHIFT_MASK);
 
         if let Some(ref mut shape) = *imp.current_shape.borrow_mut() {
-            if tool_type == ToolType::FreeHand {
+            if tool_type == ToolType::Marker && shift {
+                imp.straight_stroke.set(true);
+                shape.points.truncate(1);
                 shape.add_point(x, y);
+            } else if matches!(tool_type, ToolType::FreeHand | ToolType::Marker) {
+                if imp.straight_stroke.replace(false) {
+                    let (lx, ly) = shape.points[shape.points.len() - 1];
+                    imp.smoother
+                        .borrow_mut()
+                        .reset(imp.tool.borrow().smoothing, lx, ly);
+                }
+                if let Some((sx, sy)) = imp.smoother.borrow_mut().push(x, y) {
+                    shape.add_point(sx, sy);
+                }
             } else {
-                shape.end_x = x;
-                shape.end_y = y;
+                self.apply_drawing_constraints(shape, x, y, state);
             }
         }
 
         self.queue_draw();
     }
 
-    fn on_drag_begin(&self, screen_x: f64, screen_y: f64) {
-        let (x, y) = self.screen_to_canvas(screen_x, screen_y);
+    /// Sets the free corner of a shape being drawn. Shift keeps boxes square
+    /// and lines on 15° steps; Ctrl draws from the center instead of a corner.
+    fn apply_drawing_constraints(&self, shape: &mut Shape, x: f64, y: f64, state: ModifierType) {
+        let (ox, oy) = self.imp().draw_origin.get();
+
+        let (x, y) = if state.contains(ModifierType::SHIFT_MASK) {
+            match shape.shape_type {
+                ShapeType::Line | ShapeType::Arrow => snap_direction(ox, oy, x, y),
+                _ => constrain_square(ox, oy, x, y),
+            }
+        } else {
+            (x, y)
+        };
+
+        if state.contains(ModifierType::CONTROL_MASK) {
+            shape.start_x = 2.0 * ox - x;
+            shape.start_y = 2.0 * oy - y;
+        } else {
+            shape.start_x = ox;
+            shape.start_y = oy;
+        }
+
+        shape.end_x = x;
+        shape.end_y = y;
+    }
+
+    /// Rounds (`x`, `y`) to whole image pixels if the current tool snaps to
+    /// them.
+    fn snap_to_pixel(&self, x: f64, y: f64) -> (f64, f64) {
         let imp = self.imp();
         let tool_type = imp.tool.borrow().tool_type;
+        if imp.pixel_snap.borrow().contains(&tool_type) {
+            (x.round(), y.round())
+        } else {
+            (x, y)
+        }
+    }
 
-        if tool_type == ToolType::Select {
-            if let Some(idx) = imp.selected_index.get() {
-                let shapes = imp.shapes.borrow();
-                if idx < shapes.len() {
-                    imp.dragging.set(true);
-                    imp.drag_start_x.set(x);
-                    imp.drag_start_y.set(y);
-                    imp.drag_offset_x.set(0.0);
-                    imp.drag_offset_y.set(0.0);
+    fn snap_targets(&self, skip: &[usize]) -> SnapTargets {
+        let imp = self.imp();
+        let image_size = imp
+            .image
+            .borrow()
+            .as_ref()
+            .map(|pixbuf| (pixbuf.width() as f64, pixbuf.height() as f64));
+
+        SnapTargets::new(&imp.shapes.borrow(), skip, image_size, imp.grid_size.get())
+    }
+
+    fn store_resize_anchor(&self, shape: &Shape, handle: HandleType) {
+        let imp = self.imp();
+
+        let anchor = match shape.shape_type {
+            ShapeType::Line | ShapeType::Arrow => match handle {
+                HandleType::TopLeft | HandleType::BottomLeft => (shape.end_x, shape.end_y),
+                _ => (shape.start_x, shape.start_y),
+            },
+            _ => {
+                let (min_x, min_y, max_x, max_y) = shape.bounds();
+                match handle {
+                    HandleType::TopLeft => (max_x, max_y),
+                    HandleType::TopRight => (min_x, max_y),
+                    HandleType::BottomLeft => (max_x, min_y),
+                    _ => (min_x, min_y),
                 }
             }
+        };
+
+        let (min_x, min_y, max_x, max_y) = shape.bounds();
+        let width = max_x - min_x;
+        let height = max_y - min_y;
+
+        imp.resize_anchor.set(anchor);
+        imp.resize_aspect
+            .set(if height > 0.0 { width / height } else { 1.0 });
+    }
+
+    fn on_drag_begin(&self, screen_x: f64, screen_y: f64) {
+        let (x, y) = self.screen_to_canvas(screen_x, screen_y);
+        let imp = self.imp();
+        let tool_type = imp.tool.borrow().tool_type;
+
+        if tool_type == ToolType::Select && imp.dragging.get() && !imp.selection.borrow().is_empty()
+        {
+            imp.drag_start_x.set(x);
+            imp.drag_start_y.set(y);
+            imp.drag_offset_x.set(0.0);
+            imp.drag_offset_y.set(0.0);
+            *imp.drag_snapshot.borrow_mut() = Some(imp.shapes.borrow().clone());
         }
     }
 
-    fn on_drag_update(&self, screen_offset_x: f64, screen_offset_y: f64) {
+    fn on_drag_update(&self, screen_offset_x: f64, screen_offset_y: f64, state: ModifierType) {
         let imp = self.imp();
         let zoom = imp.zoom.get();
         let offset_x = screen_offset_x / zoom;
         let offset_y = screen_offset_y / zoom;
 
+        if let Some((x0, y0, _, _)) = imp.rubber_band.get() {
+            let (x1, y1) = (x0 + offset_x, y0 + offset_y);
+            imp.rubber_band.set(Some((x0, y0, x1, y1)));
+            self.queue_draw();
+            return;
+        }
+
         if !imp.dragging.get() {
             return;
         }
 
-        let Some(idx) = imp.selected_index.get() else {
+        let selection = imp.selection.borrow().clone();
+        if selection.is_empty() {
             return;
-        };
+        }
 
         let active_handle = imp.active_handle.get();
         let start_x = imp.drag_start_x.get();
         let start_y = imp.drag_start_y.get();
         let current_x = start_x + offset_x;
         let current_y = start_y + offset_y;
+        let shift = state.contains(ModifierType::SHIFT_MASK);
+        let snap_threshold = SNAP_DISTANCE / zoom;
+        let targets =
+            (!state.contains(ModifierType::ALT_MASK)).then(|| self.snap_targets(&selection));
+        let bounds = arrange::selection_bounds(&imp.shapes.borrow(), &selection);
 
         match active_handle {
             HandleType::None => {
+                let (offset_x, offset_y) = match (&targets, bounds) {
+                    (Some(targets), Some(bounds)) => {
+                        let (offset, guides) =
+                            targets.snap_box(bounds, offset_x, offset_y, snap_threshold);
+                        *imp.snap_guides.borrow_mut() = guides;
+                        offset
+                    }
+                    _ => {
+                        imp.snap_guides.borrow_mut().clear();
+                        (offset_x, offset_y)
+                    }
+                };
                 imp.drag_offset_x.set(offset_x);
                 imp.drag_offset_y.set(offset_y);
             }
             HandleType::Rotation => {
+                let [idx] = selection[..] else {
+                    return;
+                };
                 let mut shapes = imp.shapes.borrow_mut();
                 if let Some(shape) = shapes.get_mut(idx) {
                     let (cx, cy) = shape.center();
                     let angle = (current_y - cy).atan2(current_x - cx) + PI / 2.0;
-                    shape.set_rotation(angle);
+                    shape.set_rotation(if shift { snap_angle(angle) } else { angle });
                 }
             }
             _ => {
+                let [idx] = selection[..] else {
+                    return;
+                };
                 let corner = match active_handle {
                     HandleType::TopLeft => 0,
                     HandleType::TopRight => 1,
@@ -443,9 +749,49 @@ impl EditorCanvas {
                     _ => return,
                 };
 
+                let (current_x, current_y) = match &targets {
+                    Some(targets) => {
+                        let (point, guides) =
+                            targets.snap_point(current_x, current_y, snap_threshold);
+                        *imp.snap_guides.borrow_mut() = guides;
+                        point
+                    }
+                    None => {
+                        imp.snap_guides.borrow_mut().clear();
+                        (current_x, current_y)
+                    }
+                };
+
+                // Images keep their aspect unless Shift is held; Ctrl crops
+                // them instead of scaling.
+                let crop = state.contains(ModifierType::CONTROL_MASK);
                 let mut shapes = imp.shapes.borrow_mut();
                 if let Some(shape) = shapes.get_mut(idx) {
-                    shape.resize_corner(corner, current_x, current_y);
+                    let image = shape.shape_type == ShapeType::Image;
+                    let constrain = if image && !crop { !shift } else { shift };
+                    let (current_x, current_y) = if constrain {
+                        let (ax, ay) = imp.resize_anchor.get();
+                        match shape.shape_type {
+                            ShapeType::Line | ShapeType::Arrow => {
+                                snap_direction(ax, ay, current_x, current_y)
+                            }
+                            ShapeType::Text => (current_x, current_y),
+                            _ => constrain_aspect(
+                                ax,
+                                ay,
+                                current_x,
+                                current_y,
+                                imp.resize_aspect.get(),
+                            ),
+                        }
+                    } else {
+                        (current_x, current_y)
+                    };
+                    if image && crop {
+                        shape.crop_corner(corner, current_x, current_y);
+                    } else {
+                        shape.resize_corner(corner, current_x, current_y);
+                    }
                 }
             }
         }
@@ -453,35 +799,55 @@ impl EditorCanvas {
         self.queue_draw();
     }
 
-    fn on_drag_end(&self, screen_offset_x: f64, screen_offset_y: f64) {
+    fn on_drag_end(&self, screen_offset_x: f64, screen_offset_y: f64, state: ModifierType) {
         let imp = self.imp();
-        let zoom = imp.zoom.get();
-        let offset_x = screen_offset_x / zoom;
-        let offset_y = screen_offset_y / zoom;
+
+        if imp.rubber_band.get().is_some() {
+            self.on_drag_update(screen_offset_x, screen_offset_y, state);
+            if let Some(rect) = imp.rubber_band.take() {
+                self.select_in_rect(rect, state.contains(ModifierType::SHIFT_MASK));
+            }
+            self.queue_draw();
+            self.emit_selection_changed();
+            return;
+        }
 
         if imp.dragging.get() {
+            self.on_drag_update(screen_offset_x, screen_offset_y, state);
+
             let active_handle = imp.active_handle.get();
+            let (dx, dy) = (imp.drag_offset_x.get(), imp.drag_offset_y.get());
+            let changed = active_handle != HandleType::None || dx != 0.0 || dy != 0.0;
+            let snapshot = imp.drag_snapshot.take();
 
             if active_handle == HandleType::None {
-                if let Some(idx) = imp.selected_index.get() {
-                    let mut shapes = imp.shapes.borrow_mut();
-                    if idx < shapes.len() {
-                        let shape = &mut shapes[idx];
-                        shape.translate(offset_x, offset_y);
+                let mut shapes = imp.shapes.borrow_mut();
+                for &idx in imp.selection.borrow().iter() {
+                    if let Some(shape) = shapes.get_mut(idx) {
+                        shape.translate(dx, dy);
                     }
                 }
             }
 
+            imp.snap_guides.borrow_mut().clear();
             imp.dragging.set(false);
             imp.active_handle.set(HandleType::None);
             imp.drag_offset_x.set(0.0);
             imp.drag_offset_y.set(0.0);
             self.queue_draw();
+
+            if changed {
+                if let Some(snapshot) = snapshot {
+                    imp.history.borrow_mut().record(snapshot);
+                }
+                self.emit_shapes_changed();
+            }
         }
     }
 
-    fn on_release(&self, screen_x: f64, screen_y: f64) {
+    fn on_release(&self, screen_x: f64, screen_y: f64, state: ModifierType) {
         let (x, y) = self.screen_to_canvas(screen_x, screen_y);
+        let (x, y) = self.snap_to_pixel(x, y);
         let imp = self.imp();
 
         if !imp.drawing.get() {
@@ -491,9 +857,18 @@ impl EditorCanvas {
         imp.drawing.set(false);
 
         if let Some(mut shape) = imp.current_shape.borrow_mut().take() {
-            shape.end_x = x;
-            shape.end_y = y;
+            if imp.straight_stroke.get() {
+                shape.points.truncate(1);
+                shape.add_point(x, y);
+            } else if matches!(shape.shape_type, ShapeType::FreeHand | ShapeType::Marker) {
+                shape.add_point(x, y);
+                shape.simplify_points(simplify_epsilon(imp.tool.borrow().smoothing));
+            } else {
+                self.apply_drawing_constraints(&mut shape, x, y, state);
+            }
+            self.checkpoint();
             imp.shapes.borrow_mut().push(shape);
+            self.emit_shapes_changed();
         }
 
         self.queue_draw();
@@ -503,7 +878,7 @@ impl EditorCanvas {
         let shapes = self.imp().shapes.borrow();
 
         for (idx, shape) in shapes.iter().enumerate().rev() {
-            if shape.contains_point(x, y) {
+            if !shape.locked && !shape.hidden && shape.contains_point(x, y) {
                 return Some(idx);
             }
         }
@@ -513,7 +888,7 @@ impl EditorCanvas {
 
     fn hit_test_handle(&self, x: f64, y: f64) -> HandleType {
         let imp = self.imp();
-        let selected_idx = match imp.selected_index.get() {
+        let selected_idx = match self.single_selection() {
             Some(idx) => idx,
             None => return HandleType::None,
         };
@@ -570,8 +945,9 @@ impl EditorCanvas {
             if !text.is_empty() {
                 let tool = imp.tool.borrow();
                 let shape = tool.create_text_shape(x, y, text);
-                imp.redo_stack.borrow_mut().clear();
+                self.checkpoint();
                 imp.shapes.borrow_mut().push(shape);
+                self.emit_shapes_changed();
             }
         }
 
@@ -593,49 +969,111 @@ impl EditorCanvas {
         let zoom = imp.zoom.get();
 
         ctx.save().ok();
-        ctx.scale(zoom, zoom);
-
-        if let Some(ref pixbuf) = *imp.image.borrow() {
-            gtk4::prelude::GdkCairoContextExt::set_source_pixbuf(ctx, pixbuf, 0.0, 0.0);
-            let _ = ctx.paint();
+        // GTK can't invalidate part of a widget, so each frame at least
+        // skips whatever is scrolled out of view.
+        if let Some((x, y, width, height)) = self.visible_area() {
+            ctx.rectangle(x, y, width, height);
+            ctx.clip();
         }
+        ctx.scale(zoom, zoom);
 
-        let selected_idx = imp.selected_index.get();
+        let selection = imp.selection.borrow();
+        let show_handles = selection.len() == 1;
         let dragging = imp.dragging.get();
         let offset_x = imp.drag_offset_x.get();
         let offset_y = imp.drag_offset_y.get();
         let active_handle = imp.active_handle.get();
         let is_moving = dragging && active_handle == HandleType::None;
+        let handle_offset = if is_moving {
+            (offset_x, offset_y)
+        } else {
+            (0.0, 0.0)
+        };
 
-        for (idx, shape) in imp.shapes.borrow().iter().enumerate() {
-            let is_selected = selected_idx == Some(idx);
+        // Shapes being dragged change on every frame, so the cached layer
+        // stops below the lowest of them and everything from there up is
+        // drawn live, keeping the stacking order.
+        let shapes = imp.shapes.borrow();
+        let cached = if dragging {
+            selection.iter().copied().min().unwrap_or(shapes.len())
+        } else {
+            shapes.len()
+        }
+        .min(shapes.len());
+
+        // The layer is rendered at the zoomed device resolution, so only the
+        // screenshot in it is scaled up and the shapes stay sharp.
+        let nearest = zoom > 1.0;
+        let scale = zoom * self.scale_factor() as f64;
+        let mut render_cache = imp.render_cache.borrow_mut();
+        match render_cache.layer(&shapes[..cached], imp.generation.get(), scale, nearest) {
+            Some(layer) => {
+                let _ = ctx.set_source_surface(layer, 0.0, 0.0);
+                let _ = ctx.paint();
+            }
+            None => {
+                if let Some(base) = render_cache.base() {
+                    render::paint_base(ctx, base, nearest);
+                }
+                for shape in &shapes[..cached] {
+                    shape.draw(ctx);
+                }
+            }
+        }
+        drop(render_cache);
 
-            if is_selected && is_moving {
+        for (idx, shape) in shapes.iter().enumerate().skip(cached) {
+            if selection.contains(&idx) {
                 ctx.save().ok();
-                ctx.translate(offset_x, offset_y);
+                ctx.translate(handle_offset.0, handle_offset.1);
                 shape.draw(ctx);
                 ctx.restore().ok();
             } else {
                 shape.draw(ctx);
             }
+        }
 
-            if is_selected {
-                self.draw_selection_handles(
-                    ctx,
-                    shape,
-                    if is_moving {
-                        (offset_x, offset_y)
-                    } else {
-                        (0.0, 0.0)
-                    },
+        for &idx in selection.iter() {
+            if let Some(shape) = shapes.get(idx) {
+                self.draw_selection_handles(ctx, shape, handle_offset, show_handles);
+            }
+        }
+
+        if selection.len() > 1 {
+            if let Some(bounds) = arrange::selection_bounds(&imp.shapes.borrow(), &selection) {
+                let (dx, dy) = if is_moving {
+                    (offset_x, offset_y)
+                } else {
+                    (0.0, 0.0)
+                };
+                ctx.set_source_rgba(0.2, 0.5, 1.0, 0.8);
+                ctx.set_line_width(1.0);
+                ctx.rectangle(
+                    bounds.0 + dx - 6.0,
+                    bounds.1 + dy - 6.0,
+                    bounds.2 - bounds.0 + 12.0,
+                    bounds.3 - bounds.1 + 12.0,
                 );
+                let _ = ctx.stroke();
             }
         }
 
+        if let Some((x0, y0, x1, y1)) = imp.rubber_band.get() {
+            ctx.rectangle(x0.min(x1), y0.min(y1), (x1 - x0).abs(), (y1 - y0).abs());
+            ctx.set_source_rgba(0.2, 0.5, 1.0, 0.15);
+            let _ = ctx.fill_preserve();
+            ctx.set_source_rgba(0.2, 0.5, 1.0, 0.8);
+            ctx.set_line_width(1.0 / zoom);
+            let _ = ctx.stroke();
+        }
+
         if let Some(ref shape) = *imp.current_shape.borrow() {
             shape.draw(ctx);
         }
 
+        self.draw_pixel_grid(ctx);
+        self.draw_snap_guides(ctx);
+
         if imp.text_input_active.get() {
             if let Some((x, y)) = *imp.text_input_pos.borrow() {
                 self.draw_text_cursor(ctx, x, y);
@@ -643,9 +1081,112 @@ impl EditorCanvas {
         }
 
         ctx.restore().ok();
+
+        self.draw_loupe(ctx);
+    }
+
+    /// Magnified view of the screenshot around the pointer while the
+    /// eyedropper is active, with the pixel that would be picked outlined
+    /// and its hex value underneath.
+    fn draw_loupe(&self, ctx: &cairo::Context) {
+        if !self.eyedropper_active() {
+            return;
+        }
+        let imp = self.imp();
+        let Some((x, y)) = imp.pointer.get() else {
+            return;
+        };
+        let Some(color) = self.pixel_at(x, y) else {
+            return;
+        };
+        let render_cache = imp.render_cache.borrow();
+        let Some(base) = render_cache.base() else {
+            return;
+        };
+
+        let zoom = imp.zoom.get();
+        let size = LOUPE_PIXELS as f64 * LOUPE_SCALE;
+        let half = (LOUPE_PIXELS / 2) as f64;
+        let label_height = 20.0;
+
+        // Below and to the right of the pointer, flipped when that would
+        // leave the visible part of the canvas.
+        let (screen_x, screen_y) = (x * zoom, y * zoom);
+        let mut left = screen_x + LOUPE_OFFSET;
+        let mut top = screen_y + LOUPE_OFFSET;
+        if let Some((vx, vy, width, height)) = self.visible_area() {
+            if left + size > vx + width {
+                left = screen_x - LOUPE_OFFSET - size;
+            }
+            if top + size + label_height > vy + height {
+                top = screen_y - LOUPE_OFFSET - size - label_height;
+            }
+        }
+
+        ctx.save().ok();
+        ctx.rectangle(left, top, size, size);
+        ctx.clip();
+        ctx.set_source_rgb(0.15, 0.15, 0.15);
+        let _ = ctx.paint();
+        ctx.translate(left, top);
+        ctx.scale(LOUPE_SCALE, LOUPE_SCALE);
+        ctx.translate(half - x.floor(), half - y.floor());
+        let _ = ctx.set_source_surface(base, 0.0, 0.0);
+        ctx.source().set_filter(cairo::Filter::Nearest);
+        let _ = ctx.paint();
+        ctx.restore().ok();
+
+        let center_x = left + half * LOUPE_SCALE;
+        let center_y = top + half * LOUPE_SCALE;
+        ctx.set_line_width(1.0);
+        ctx.rectangle(
+            center_x - 0.5,
+            center_y - 0.5,
+            LOUPE_SCALE + 1.0,
+            LOUPE_SCALE + 1.0,
+        );
+        ctx.set_source_rgb(0.0, 0.0, 0.0);
+        let _ = ctx.stroke();
+        ctx.rectangle(
+            center_x + 0.5,
+            center_y + 0.5,
+            LOUPE_SCALE - 1.0,
+            LOUPE_SCALE - 1.0,
+        );
+        ctx.set_source_rgb(1.0, 1.0, 1.0);
+        let _ = ctx.stroke();
+
+        ctx.rectangle(left - 0.5, top - 0.5, size + 1.0, size + label_height + 1.0);
+        ctx.set_source_rgba(0.0, 0.0, 0.0, 0.8);
+        let _ = ctx.stroke();
+
+        ctx.rectangle(left, top + size, size, label_height);
+        ctx.set_source_rgba(0.0, 0.0, 0.0, 0.8);
+        let _ = ctx.fill();
+
+        let (r, g, b) = color.to_rgb8();
+        ctx.rectangle(left + 5.0, top + size + 5.0, 10.0, 10.0);
+        ctx.set_source_rgb(r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+        let _ = ctx.fill();
+
+        ctx.select_font_face(
+            "monospace",
+            cairo::FontSlant::Normal,
+            cairo::FontWeight::Normal,
+        );
+        ctx.set_font_size(12.0);
+        ctx.set_source_rgb(1.0, 1.0, 1.0);
+        ctx.move_to(left + 22.0, top + size + 14.0);
+        let _ = ctx.show_text(&color.to_hex());
     }
 
-    fn draw_selection_handles(&self, ctx: &cairo::Context, shape: &Shape, offset: (f64, f64)) {
+    fn draw_selection_handles(
+        &self,
+        ctx: &cairo::Context,
+        shape: &Shape,
+        offset: (f64, f64),
+        show_handles: bool,
+    ) {
         let bounds = shape.bounds();
         let (min_x, min_y, max_x, max_y) = (
             bounds.0 + offset.0,
@@ -676,39 +1217,110 @@ impl EditorCanvas {
         let _ = ctx.stroke();
         ctx.set_dash(&[], 0.0);
 
-        let handle_size = 6.0;
-        ctx.set_source_rgba(0.2, 0.5, 1.0, 1.0);
+        if show_handles {
+            let handle_size = 6.0;
+            ctx.set_source_rgba(0.2, 0.5, 1.0, 1.0);
+
+            for (hx, hy) in [
+                (min_x, min_y),
+                (max_x, min_y),
+                (min_x, max_y),
+                (max_x, max_y),
+            ] {
+                ctx.rectangle(
+                    hx - handle_size / 2.0,
+                    hy - handle_size / 2.0,
+                    handle_size,
+                    handle_size,
+                );
+                let _ = ctx.fill();
+            }
 
-        for (hx, hy) in [
-            (min_x, min_y),
-            (max_x, min_y),
-            (min_x, max_y),
-            (max_x, max_y),
-        ] {
-            ctx.rectangle(
-                hx - handle_size / 2.0,
-                hy - handle_size / 2.0,
-                handle_size,
-                handle_size,
-            );
+            let center_x = (min_x + max_x) / 2.0;
+            let rotation_y = min_y - 25.0;
+
+            ctx.set_source_rgba(0.2, 0.7, 0.3, 1.0);
+            ctx.set_line_width(1.5);
+            ctx.move_to(center_x, min_y);
+            ctx.line_to(center_x, rotation_y + 6.0);
+            let _ = ctx.stroke();
+
+            ctx.arc(center_x, rotation_y, 6.0, 0.0, 2.0 * PI);
             let _ = ctx.fill();
         }
 
-        let center_x = (min_x + max_x) / 2.0;
-        let rotation_y = min_y - 25.0;
+        if has_rotation {
+            ctx.restore().ok();
+        }
+    }
 
-        ctx.set_source_rgba(0.2, 0.7, 0.3, 1.0);
-        ctx.set_line_width(1.5);
-        ctx.move_to(center_x, min_y);
-        ctx.line_to(center_x, rotation_y + 6.0);
+    /// Outlines every image pixel in the visible area at high zoom.
+    fn draw_pixel_grid(&self, ctx: &cairo::Context) {
+        let imp = self.imp();
+        let zoom = imp.zoom.get();
+        if !imp.pixel_grid.get() || zoom < PIXEL_GRID_ZOOM {
+            return;
+        }
+        let Some((width, height)) = self.image_size() else {
+            return;
+        };
+        let Ok((x0, y0, x1, y1)) = ctx.clip_extents() else {
+            return;
+        };
+
+        let x0 = x0.floor().max(0.0);
+        let y0 = y0.floor().max(0.0);
+        let x1 = x1.ceil().min(width as f64);
+        let y1 = y1.ceil().min(height as f64);
+
+        let mut x = x0;
+        while x <= x1 {
+            ctx.move_to(x, y0);
+            ctx.line_to(x, y1);
+            x += 1.0;
+        }
+        let mut y = y0;
+        while y <= y1 {
+            ctx.move_to(x0, y);
+            ctx.line_to(x1, y);
+            y += 1.0;
+        }
+
+        ctx.set_source_rgba(0.5, 0.5, 0.5, 0.4);
+        ctx.set_line_width(1.0 / zoom);
         let _ = ctx.stroke();
+    }
 
-        ctx.arc(center_x, rotation_y, 6.0, 0.0, 2.0 * PI);
-        let _ = ctx.fill();
+    fn draw_snap_guides(&self, ctx: &cairo::Context) {
+        let imp = self.imp();
+        let guides = imp.snap_guides.borrow();
+        if guides.is_empty() {
+            return;
+        }
 
-        if has_rotation {
-            ctx.restore().ok();
+        let (width, height) = match *imp.image.borrow() {
+            Some(ref pixbuf) => (pixbuf.width() as f64, pixbuf.height() as f64),
+            None => return,
+        };
+
+        ctx.save().ok();
+        ctx.set_source_rgba(1.0, 0.2, 0.8, 0.9);
+        ctx.set_line_width(1.0 / imp.zoom.get());
+
+        for guide in guides.iter() {
+            match *guide {
+                SnapGuide::Vertical(x) => {
+                    ctx.move_to(x, 0.0);
+                    ctx.line_to(x, height);
+                }
+                SnapGuide::Horizontal(y) => {
+                    ctx.move_to(0.0, y);
+                    ctx.line_to(width, y);
+                }
+            }
         }
+        let _ = ctx.stroke();
+        ctx.restore().ok();
     }
 
     fn draw_text_cursor(&self, ctx: &cairo::Context, x: f64, y: f64) {
@@ -745,102 +1357,823 @@ impl EditorCanvas {
         if let Ok(pixbuf) = Pixbuf::from_stream(&stream, Cancellable::NONE) {
             self.set_content_width(pixbuf.width());
             self.set_content_height(pixbuf.height());
+            self.imp()
+                .render_cache
+                .borrow_mut()
+                .set_image(Some(&pixbuf));
             *self.imp().image.borrow_mut() = Some(pixbuf);
             self.imp().shapes.borrow_mut().clear();
-            self.imp().redo_stack.borrow_mut().clear();
-            self.imp().selected_index.set(None);
+            self.imp().history.borrow_mut().clear();
+            self.imp().selection.borrow_mut().clear();
             self.queue_draw();
+            self.emit_shapes_changed();
+            self.emit_view_changed();
+            self.imp().dirty.set(false);
+            self.fit_view(FitMode::Shrink);
         }
     }
 
-    pub fn set_tool_type(&self, tool_type: ToolType) {
-        let imp = self.imp();
-
-        if imp.text_input_active.get() {
-            self.finish_text_input();
-        }
-
-        imp.tool.borrow_mut().tool_type = tool_type;
-        imp.selected_index.set(None);
-        self.queue_draw();
+    /// The screenshot without annotations.
+    pub fn image(&self) -> Option<Pixbuf> {
+        self.imp().image.borrow().clone()
     }
 
-    pub fn set_color(&self, color: super::shapes::Color) {
-        self.imp().tool.borrow_mut().color = color;
+    /// Size of the screenshot in image pixels.
+    pub fn image_size(&self) -> Option<(i32, i32)> {
+        let image = self.imp().image.borrow();
+        image
+            .as_ref()
+            .map(|pixbuf| (pixbuf.width(), pixbuf.height()))
     }
 
-    pub fn set_stroke_width(&self, width: f64) {
-        self.imp().tool.borrow_mut().stroke_width = width;
+    /// Pointer position in image pixels while it is over the canvas.
+    pub fn pointer_position(&self) -> Option<(f64, f64)> {
+        self.imp().pointer.get()
     }
 
-    #[allow(dead_code)]
-    pub fn set_filled(&self, filled: bool) {
-        self.imp().tool.borrow_mut().filled = filled;
+    /// Color of the screenshot pixel at (`x`, `y`), ignoring annotations.
+    pub fn pixel_at(&self, x: f64, y: f64) -> Option<Color> {
+        let image = self.imp().image.borrow();
+        let pixbuf = image.as_ref()?;
+        let (x, y) = (x.floor() as i32, y.floor() as i32);
+        if x < 0 || y < 0 || x >= pixbuf.width() || y >= pixbuf.height() {
+            return None;
+        }
+
+        let pixel = pixbuf.new_subpixbuf(x, y, 1, 1).read_pixel_bytes();
+        let alpha = if pixbuf.has_alpha() { pixel[3] } else { 255 };
+        Some(Color::new(
+            pixel[0] as f64 / 255.0,
+            pixel[1] as f64 / 255.0,
+            pixel[2] as f64 / 255.0,
+            alpha as f64 / 255.0,
+        ))
     }
 
-    pub fn clear_shapes(&self) {
+    /// Whether a click picks a color: with the eyedropper tool, or while Alt
+    /// is held with a drawing tool. Alt keeps its meaning with Select, where
+    /// it turns off snapping.
+    fn eyedropper_active(&self) -> bool {
         let imp = self.imp();
-        let shapes = imp.shapes.borrow().clone();
-        if !shapes.is_empty() {
-            imp.redo_stack.borrow_mut().extend(shapes);
-            imp.shapes.borrow_mut().clear();
+        match imp.tool.borrow().tool_type {
+            ToolType::Eyedropper => true,
+            ToolType::Select => false,
+            _ => imp.eyedropper_held.get() && !imp.drawing.get(),
         }
-        imp.selected_index.set(None);
-        self.queue_draw();
     }
 
-    pub fn undo(&self) {
+    fn set_eyedropper_held(&self, held: bool) {
+        if self.imp().eyedropper_held.replace(held) != held {
+            self.queue_draw();
+        }
+    }
+
+    /// Makes the screenshot color at (`x`, `y`) the tool color and reports
+    /// it as `#rrggbb`.
+    fn pick_color(&self, x: f64, y: f64) {
+        let Some(color) = self.pixel_at(x, y) else {
+            return;
+        };
+        let color = Color { a: 1.0, ..color };
+        self.imp().tool.borrow_mut().color = color;
+        self.emit_by_name::<()>("color-picked", &[&color.to_hex()]);
+    }
+
+    pub fn set_tool_type(&self, tool_type: ToolType) {
         let imp = self.imp();
-        if let Some(shape) = imp.shapes.borrow_mut().pop() {
-            imp.redo_stack.borrow_mut().push(shape);
+
+        if imp.text_input_active.get() {
+            self.finish_text_input();
         }
-        imp.selected_index.set(None);
+
+        imp.tool.borrow_mut().tool_type = tool_type;
+        imp.selection.borrow_mut().clear();
         self.queue_draw();
+        self.emit_selection_changed();
     }
 
-    pub fn redo(&self) {
+    pub fn set_color(&self, color: Color) {
+        self.imp().tool.borrow_mut().color = color;
+        self.edit_selected("color", |shape| shape.color = color);
+    }
+
+    pub fn set_stroke_width(&self, width: f64) {
+        self.imp().tool.borrow_mut().stroke_width = width;
+        self.edit_selected("stroke_width", |shape| {
+            shape.stroke_width = shape_stroke_width(shape.shape_type, width);
+        });
+    }
+
+    pub fn set_font_size(&self, size: f64) {
+        self.imp().tool.borrow_mut().font_size = size;
+        self.edit_selected("font_size", |shape| {
+            if shape.shape_type == ShapeType::Text {
+                shape.font_size = size;
+            }
+        });
+    }
+
+    pub fn set_opacity(&self, opacity: f64) {
+        self.imp().tool.borrow_mut().opacity = opacity;
+        self.edit_selected("opacity", |shape| shape.opacity = opacity);
+    }
+
+    pub fn set_smoothing(&self, smoothing: f64) {
+        self.imp().tool.borrow_mut().smoothing = smoothing;
+    }
+
+    /// Shows a grid around each image pixel when zoomed in to 800% or more.
+    pub fn set_pixel_grid(&self, visible: bool) {
+        self.imp().pixel_grid.set(visible);
+        self.queue_draw();
+    }
+
+    /// Makes new shapes drawn with `tools` start and end on whole pixels.
+    pub fn set_pixel_snap(&self, tools: Vec<ToolType>) {
+        *self.imp().pixel_snap.borrow_mut() = tools;
+    }
+
+    pub fn set_grid_size(&self, grid_size: Option<f64>) {
+        self.imp().grid_size.set(grid_size);
+    }
+
+    pub fn set_fill_mode(&self, fill: FillMode) {
+        self.imp().tool.borrow_mut().fill = fill;
+        self.edit_selected("fill", |shape| {
+            if shape.is_fillable() {
+                shape.fill = fill;
+            }
+        });
+    }
+
+    pub fn set_fill_color(&self, color: Color) {
+        self.imp().tool.borrow_mut().fill_color = color;
+        self.edit_selected("fill_color", |shape| {
+            if shape.is_fillable() {
+                shape.fill_color = color;
+            }
+        });
+    }
+
+    pub fn set_dash(&self, dash: DashStyle) {
+        self.imp().tool.borrow_mut().dash = dash;
+        self.edit_selected("dash", |shape| shape.dash = dash);
+    }
+
+    pub fn set_line_cap(&self, cap: StrokeCap) {
+        self.imp().tool.borrow_mut().cap = cap;
+        self.edit_selected("cap", |shape| shape.cap = cap);
+    }
+
+    pub fn set_line_join(&self, join: StrokeJoin) {
+        self.imp().tool.borrow_mut().join = join;
+        self.edit_selected("join", |shape| shape.join = join);
+    }
+
+    pub fn set_corner_radius(&self, radius: f64) {
+        self.imp().tool.borrow_mut().corner_radius = radius;
+        self.edit_selected("corner_radius", |shape| {
+            if shape.is_fillable() {
+                shape.corner_radius = radius;
+            }
+        });
+    }
+
+    pub fn clear_shapes(&self) {
         let imp = self.imp();
-        if let Some(shape) = imp.redo_stack.borrow_mut().pop() {
-            imp.shapes.borrow_mut().push(shape);
+        if !imp.shapes.borrow().is_empty() {
+            self.checkpoint();
+            imp.shapes.borrow_mut().clear();
         }
+        imp.selection.borrow_mut().clear();
+        self.queue_draw();
+        self.emit_shapes_changed();
+        self.emit_selection_changed();
+    }
+
+    pub fn undo(&self) {
+        let imp = self.imp();
+        let current = imp.shapes.borrow().clone();
+        let previous = imp.history.borrow_mut().undo(current);
+        self.restore_shapes(previous);
+    }
+
+    pub fn redo(&self) {
+        let imp = self.imp();
+        let current = imp.shapes.borrow().clone();
+        let next = imp.history.borrow_mut().redo(current);
+        self.restore_shapes(next);
+    }
+
+    fn restore_shapes(&self, shapes: Option<Vec<Shape>>) {
+        let Some(shapes) = shapes else {
+            return;
+        };
+
+        let imp = self.imp();
+        *imp.shapes.borrow_mut() = shapes;
+        imp.selection.borrow_mut().clear();
         self.queue_draw();
+        self.emit_shapes_changed();
+        self.emit_selection_changed();
     }
 
     pub fn delete_selected(&self) {
         let imp = self.imp();
-        if let Some(idx) = imp.selected_index.get() {
-            let mut shapes = imp.shapes.borrow_mut();
+        if imp.selection.borrow().is_empty() {
+            return;
+        }
+
+        self.checkpoint();
+        let selection = imp.selection.take();
+        let mut shapes = imp.shapes.borrow_mut();
+        for &idx in selection.iter().rev() {
             if idx < shapes.len() {
-                let removed = shapes.remove(idx);
-                imp.redo_stack.borrow_mut().push(removed);
-                imp.selected_index.set(None);
+                shapes.remove(idx);
             }
         }
+        drop(shapes);
         self.queue_draw();
+        self.emit_shapes_changed();
+        self.emit_selection_changed();
     }
 
-    pub fn get_image_data(&self) -> Option<Vec<u8>> {
+    /// Drops the shape being drawn, or clears the selection when idle.
+    pub fn cancel(&self) {
         let imp = self.imp();
-        let pixbuf = imp.image.borrow();
-        let pixbuf = pixbuf.as_ref()?;
 
-        let width = pixbuf.width();
-        let height = pixbuf.height();
+        if imp.text_input_active.get() {
+            self.cancel_text_input();
+        } else if imp.drawing.replace(false) {
+            imp.current_shape.borrow_mut().take();
+            imp.straight_stroke.set(false);
+            self.queue_draw();
+        } else if !imp.selection.borrow().is_empty() {
+            imp.selection.borrow_mut().clear();
+            self.queue_draw();
+            self.emit_selection_changed();
+        }
+    }
 
-        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).ok()?;
-        let ctx = cairo::Context::new(&surface).ok()?;
+    pub fn select_all(&self) {
+        let imp = self.imp();
+        let shapes = imp.shapes.borrow();
+        let selection = arrange::selectable(&shapes, (0..shapes.len()).collect());
+        drop(shapes);
+        *imp.selection.borrow_mut() = selection;
+        self.queue_draw();
+        self.emit_selection_changed();
+    }
 
-        gtk4::prelude::GdkCairoContextExt::set_source_pixbuf(&ctx, pixbuf, 0.0, 0.0);
-        let _ = ctx.paint();
+    pub fn selection(&self) -> Vec<usize> {
+        self.imp().selection.borrow().clone()
+    }
+
+    /// Selects the shapes at `selection`, leaving out locked and hidden ones
+    /// as clicking and the rubber band do.
+    pub fn set_selection(&self, selection: Vec<usize>) {
+        let imp = self.imp();
+        let requested = selection.len();
+        let selection = arrange::selectable(&imp.shapes.borrow(), selection);
+
+        // When something was left out, the change is still reported so a
+        // panel that asked for it shows the actual selection again.
+        if *imp.selection.borrow() == selection && selection.len() == requested {
+            return;
+        }
+
+        *imp.selection.borrow_mut() = selection;
+        self.queue_draw();
+        self.emit_selection_changed();
+    }
+
+    pub fn shapes(&self) -> Vec<Shape> {
+        self.imp().shapes.borrow().clone()
+    }
+
+    /// The annotations as JSON, for autosaving.
+    pub fn shapes_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&*self.imp().shapes.borrow())
+    }
+
+    /// Replaces the annotations with ones from `shapes_json`. They count as
+    /// unsaved changes.
+    pub fn load_shapes_json(&self, json: &str) -> serde_json::Result<()> {
+        let shapes: Vec<Shape> = serde_json::from_str(json)?;
+        let imp = self.imp();
+        *imp.shapes.borrow_mut() = shapes;
+        imp.history.borrow_mut().clear();
+        imp.selection.borrow_mut().clear();
+        self.queue_draw();
+        self.emit_shapes_changed();
+        self.emit_selection_changed();
+        Ok(())
+    }
+
+    /// Whether the annotations changed since the image was opened or saved.
+    pub fn is_dirty(&self) -> bool {
+        self.imp().dirty.get()
+    }
+
+    pub fn mark_clean(&self) {
+        self.imp().dirty.set(false);
+    }
+
+    pub fn shape_count(&self) -> usize {
+        self.imp().shapes.borrow().len()
+    }
+
+    /// First selected shape, whose style the property controls show.
+    pub fn selected_shape(&self) -> Option<Shape> {
+        let imp = self.imp();
+        let idx = *imp.selection.borrow().first()?;
+        imp.shapes.borrow().get(idx).cloned()
+    }
 
-        for shape in imp.shapes.borrow().iter() {
-            shape.draw(&ctx);
+    fn selected_shapes(&self) -> Vec<Shape> {
+        let imp = self.imp();
+        let shapes = imp.shapes.borrow();
+        imp.selection
+            .borrow()
+            .iter()
+            .filter_map(|&idx| shapes.get(idx).cloned())
+            .collect()
+    }
+
+    /// Copies the selected shapes to the clipboard. Returns false when
+    /// nothing is selected, so the caller can copy the image instead.
+    pub fn copy_selected(&self) -> bool {
+        let shapes = self.selected_shapes();
+        if shapes.is_empty() {
+            return false;
+        }
+
+        self.imp().paste_count.set(0);
+        clipboard::write_shapes(&self.clipboard(), &shapes)
+    }
+
+    pub fn cut_selected(&self) -> bool {
+        if !self.copy_selected() {
+            return false;
         }
 
-        let _ = pixbuf;
+        self.delete_selected();
+        true
+    }
+
+    /// Pastes shapes from the clipboard, centered on the pointer when it is
+    /// over the canvas and offset from the originals otherwise.
+    pub fn paste(&self) {
+        let canvas = self.clone();
+        glib::MainContext::default().spawn_local(async move {
+            let Some(shapes) = clipboard::read_shapes(&canvas.clipboard()).await else {
+                if let Some(pixbuf) = clipboard::read_image(&canvas.clipboard()).await {
+                    canvas.insert_image(pixbuf, None);
+                }
+                return;
+            };
+
+            let imp = canvas.imp();
+            let all: Vec<usize> = (0..shapes.len()).collect();
+            let (dx, dy) = match (imp.pointer.get(), arrange::selection_bounds(&shapes, &all)) {
+                (Some((px, py)), Some((x0, y0, x1, y1))) => {
+                    (px - (x0 + x1) / 2.0, py - (y0 + y1) / 2.0)
+                }
+                _ => {
+                    let count = imp.paste_count.get() + 1;
+                    imp.paste_count.set(count);
+                    let offset = PASTE_OFFSET * count as f64;
+                    (offset, offset)
+                }
+            };
+
+            canvas.insert_shapes(shapes, dx, dy);
+        });
+    }
+
+    /// Adds `pixbuf` as an image layer centered on `at`, or on the pointer,
+    /// scaled down to fit inside the screenshot.
+    pub fn insert_image(&self, pixbuf: Pixbuf, at: Option<(f64, f64)>) {
+        let imp = self.imp();
+        let width = pixbuf.width() as f64;
+        let height = pixbuf.height() as f64;
+        let canvas_size = imp
+            .image
+            .borrow()
+            .as_ref()
+            .map(|base| (base.width() as f64, base.height() as f64));
+
+        let scale = canvas_size.map_or(1.0, |(cw, ch)| {
+            (cw * 0.8 / width).min(ch * 0.8 / height).min(1.0)
+        });
+        let (cx, cy) = at
+            .or(imp.pointer.get())
+            .or(canvas_size.map(|(cw, ch)| (cw / 2.0, ch / 2.0)))
+            .unwrap_or((width / 2.0, height / 2.0));
+        let (half_w, half_h) = (width * scale / 2.0, height * scale / 2.0);
+
+        let shape = Shape {
+            shape_type: ShapeType::Image,
+            start_x: cx - half_w,
+            start_y: cy - half_h,
+            end_x: cx + half_w,
+            end_y: cy + half_h,
+            opacity: imp.tool.borrow().opacity,
+            image: Some(pixbuf),
+            ..Default::default()
+        };
+        self.insert_shapes(vec![shape], 0.0, 0.0);
+    }
+
+    /// Bounds of the selection as `(x, y, width, height)`.
+    pub fn selection_geometry(&self) -> Option<(f64, f64, f64, f64)> {
+        let imp = self.imp();
+        let (x0, y0, x1, y1) =
+            arrange::selection_bounds(&imp.shapes.borrow(), &imp.selection.borrow())?;
+        Some((x0, y0, x1 - x0, y1 - y0))
+    }
+
+    /// Moves the selection so its bounds start at `(x, y)`.
+    pub fn set_selection_position(&self, x: f64, y: f64) {
+        let Some((x0, y0, _, _)) = self.selection_geometry() else {
+            return;
+        };
+        let (dx, dy) = (x - x0, y - y0);
+        self.edit_selected("position", |shape| shape.translate(dx, dy));
+    }
+
+    /// Scales the selection about its top-left corner to `width` x `height`.
+    /// A horizontal or vertical line has no extent on one axis, which is
+    /// left as it is so the line's length can still be set.
+    pub fn set_selection_size(&self, width: f64, height: f64) {
+        let Some((x0, y0, w, h)) = self.selection_geometry() else {
+            return;
+        };
+        if w <= 0.0 && h <= 0.0 {
+            return;
+        }
+        let scale = |target: f64, current: f64| {
+            if current > 0.0 {
+                (target >= 1.0).then(|| target / current)
+            } else {
+                Some(1.0)
+            }
+        };
+        let (Some(sx), Some(sy)) = (scale(width, w), scale(height, h)) else {
+            return;
+        };
+
+        self.edit_selected("size", |shape| shape.scale_from(x0, y0, sx, sy));
+    }
+
+    /// Sets the rotation of every selected shape, in degrees.
+    pub fn set_selection_rotation(&self, degrees: f64) {
+        let angle = degrees.to_radians();
+        self.edit_selected("rotation", |shape| shape.set_rotation(angle));
+    }
+
+    pub fn duplicate_selected(&self) {
+        let shapes = self.selected_shapes();
+        if !shapes.is_empty() {
+            self.insert_shapes(shapes, PASTE_OFFSET, PASTE_OFFSET);
+        }
+    }
+
+    /// Adds `shapes` on top of the others, moved by `(dx, dy)`, and selects
+    /// them. Copied groups become new groups.
+    fn insert_shapes(&self, mut shapes: Vec<Shape>, dx: f64, dy: f64) {
+        let imp = self.imp();
+        let next_group = imp
+            .shapes
+            .borrow()
+            .iter()
+            .filter_map(|s| s.group)
+            .max()
+            .map_or(0, |g| g + 1);
+
+        let mut groups: Vec<u32> = Vec::new();
+        for shape in &mut shapes {
+            shape.translate(dx, dy);
+            shape.locked = false;
+            shape.hidden = false;
+            if let Some(group) = shape.group {
+                let position = groups.iter().position(|&g| g == group).unwrap_or_else(|| {
+                    groups.push(group);
+                    groups.len() - 1
+                });
+                shape.group = Some(next_group + position as u32);
+            }
+        }
+
+        self.checkpoint();
+        let start = imp.shapes.borrow().len();
+        imp.shapes.borrow_mut().extend(shapes);
+        let end = imp.shapes.borrow().len();
+        *imp.selection.borrow_mut() = (start..end).collect();
+
+        self.queue_draw();
+        self.emit_shapes_changed();
+        self.emit_selection_changed();
+    }
+
+    /// Puts every selected shape into one new persistent group.
+    pub fn group_selected(&self) {
+        let imp = self.imp();
+        if imp.selection.borrow().len() < 2 {
+            return;
+        }
+
+        let group = imp
+            .shapes
+            .borrow()
+            .iter()
+            .filter_map(|s| s.group)
+            .max()
+            .map_or(0, |g| g + 1);
+        self.checkpoint();
+        if self.update_selected(|shape| shape.group = Some(group)) {
+            self.emit_shapes_changed();
+        }
+    }
+
+    pub fn ungroup_selected(&self) {
+        if self.imp().selection.borrow().is_empty() {
+            return;
+        }
+
+        self.checkpoint();
+        if self.update_selected(|shape| shape.group = None) {
+            self.emit_shapes_changed();
+        }
+    }
+
+    pub fn align_selected(&self, alignment: Alignment) {
+        let imp = self.imp();
+        if imp.selection.borrow().is_empty() {
+            return;
+        }
+
+        self.checkpoint();
+        arrange::align(
+            &mut imp.shapes.borrow_mut(),
+            &imp.selection.borrow(),
+            alignment,
+        );
+        self.queue_draw();
+        self.emit_shapes_changed();
+    }
+
+    pub fn distribute_selected(&self, distribution: Distribution) {
+        let imp = self.imp();
+        if imp.selection.borrow().len() < 3 {
+            return;
+        }
+
+        self.checkpoint();
+        arrange::distribute(
+            &mut imp.shapes.borrow_mut(),
+            &imp.selection.borrow(),
+            distribution,
+        );
+        self.queue_draw();
+        self.emit_shapes_changed();
+    }
+
+    pub fn reorder_selected(&self, order: ZOrder) {
+        let imp = self.imp();
+        if imp.selection.borrow().is_empty() {
+            return;
+        }
+
+        self.checkpoint();
+        let selection =
+            arrange::reorder(&mut imp.shapes.borrow_mut(), &imp.selection.borrow(), order);
+        *imp.selection.borrow_mut() = selection;
+        self.queue_draw();
+        self.emit_shapes_changed();
+    }
+
+    /// Moves the shape at `from` so it ends up at index `to` in drawing order.
+    pub fn move_shape(&self, from: usize, to: usize) {
+        let imp = self.imp();
+        {
+            let mut shapes = imp.shapes.borrow_mut();
+            if from >= shapes.len() || to >= shapes.len() || from == to {
+                return;
+            }
+            imp.history.borrow_mut().record(shapes.clone());
+            let shape = shapes.remove(from);
+            shapes.insert(to, shape);
+        }
+        *imp.selection.borrow_mut() = vec![to];
+        self.queue_draw();
+        self.emit_shapes_changed();
+    }
+
+    pub fn set_shape_hidden(&self, idx: usize, hidden: bool) {
+        let imp = self.imp();
+        self.checkpoint();
+        if let Some(shape) = imp.shapes.borrow_mut().get_mut(idx) {
+            shape.hidden = hidden;
+        }
+        if hidden {
+            imp.selection.borrow_mut().retain(|&i| i != idx);
+        }
+        self.queue_draw();
+        self.emit_shapes_changed();
+        self.emit_selection_changed();
+    }
+
+    /// Locked shapes can't be picked, so they also leave the selection
+    /// rather than staying draggable and editable.
+    pub fn set_shape_locked(&self, idx: usize, locked: bool) {
+        let imp = self.imp();
+        self.checkpoint();
+        if let Some(shape) = imp.shapes.borrow_mut().get_mut(idx) {
+            shape.locked = locked;
+        }
+        if locked {
+            imp.selection.borrow_mut().retain(|&i| i != idx);
+        }
+        self.queue_draw();
+        self.emit_shapes_changed();
+        self.emit_selection_changed();
+    }
+
+    pub fn rename_shape(&self, idx: usize, name: &str) {
+        self.checkpoint();
+        if let Some(shape) = self.imp().shapes.borrow_mut().get_mut(idx) {
+            shape.name = name.trim().to_string();
+        }
+        self.emit_shapes_changed();
+    }
+
+    pub fn connect_shapes_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_local("shapes-changed", false, move |values| {
+            let canvas = values[0].get::<Self>().ok()?;
+            f(&canvas);
+            None
+        })
+    }
+
+    pub fn connect_selection_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_local("selection-changed", false, move |values| {
+            let canvas = values[0].get::<Self>().ok()?;
+            f(&canvas);
+            None
+        })
+    }
+
+    /// Emitted when the zoom, the image or the pointer position changes.
+    pub fn connect_view_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_local("view-changed", false, move |values| {
+            let canvas = values[0].get::<Self>().ok()?;
+            f(&canvas);
+            None
+        })
+    }
+
+    /// Emitted with the `#rrggbb` value when the eyedropper picks a color.
+    pub fn connect_color_picked<F: Fn(&Self, &str) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_local("color-picked", false, move |values| {
+            let canvas = values[0].get::<Self>().ok()?;
+            let hex = values[1].get::<String>().ok()?;
+            f(&canvas, &hex);
+            None
+        })
+    }
+
+    fn emit_shapes_changed(&self) {
+        let imp = self.imp();
+        imp.generation.set(imp.generation.get().wrapping_add(1));
+        imp.dirty.set(true);
+        self.emit_by_name::<()>("shapes-changed", &[]);
+    }
+
+    fn emit_selection_changed(&self) {
+        // Edits made after reselecting the same shapes are a new step.
+        self.imp().history.borrow_mut().break_merge();
+        self.emit_by_name::<()>("selection-changed", &[]);
+    }
+
+    fn emit_view_changed(&self) {
+        self.emit_by_name::<()>("view-changed", &[]);
+    }
+
+    /// Saves the current shapes as an undo step. This also ends any run of
+    /// merged style edits.
+    fn checkpoint(&self) {
+        let imp = self.imp();
+        let snapshot = imp.shapes.borrow().clone();
+        let mut history = imp.history.borrow_mut();
+        history.break_merge();
+        history.record(snapshot);
+    }
+
+    /// Applies a style change to the selection as one live, undoable edit.
+    /// Repeated changes under the same `key` merge into a single undo step.
+    fn edit_selected(&self, key: &'static str, f: impl Fn(&mut Shape)) {
+        let imp = self.imp();
+        let snapshot = imp.shapes.borrow().clone();
+        if !self.update_selected(f) || *imp.shapes.borrow() == snapshot {
+            return;
+        }
+
+        imp.history
+            .borrow_mut()
+            .record_merged(key, &imp.selection.borrow(), snapshot);
+        self.emit_shapes_changed();
+    }
+
+    /// Applies `f` to every selected shape. Returns whether anything was
+    /// selected.
+    fn update_selected(&self, f: impl Fn(&mut Shape)) -> bool {
+        let imp = self.imp();
+        let selection = imp.selection.borrow();
+        if selection.is_empty() {
+            return false;
+        }
+
+        let mut shapes = imp.shapes.borrow_mut();
+        for &idx in selection.iter() {
+            if let Some(shape) = shapes.get_mut(idx) {
+                f(shape);
+            }
+        }
+        drop(shapes);
+        drop(selection);
+        self.queue_draw();
+        true
+    }
+
+    fn single_selection(&self) -> Option<usize> {
+        match self.imp().selection.borrow()[..] {
+            [idx] => Some(idx),
+            _ => None,
+        }
+    }
+
+    /// Indices of every shape that shares a group with `idx`, including it.
+    /// The selectable shapes of the group `idx` belongs to, or just `idx`.
+    fn group_members(&self, idx: usize) -> Vec<usize> {
+        let shapes = self.imp().shapes.borrow();
+        let members = match shapes.get(idx).and_then(|shape| shape.group) {
+            Some(group) => shapes
+                .iter()
+                .enumerate()
+                .filter(|(_, shape)| shape.group == Some(group))
+                .map(|(i, _)| i)
+                .collect(),
+            None => vec![idx],
+        };
+        arrange::selectable(&shapes, members)
+    }
+
+    fn select_in_rect(&self, rect: (f64, f64, f64, f64), extend: bool) {
+        let imp = self.imp();
+        let (x0, y0, x1, y1) = rect;
+        let (min_x, min_y, max_x, max_y) = (x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1));
+
+        let hits: Vec<usize> = imp
+            .shapes
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(_, shape)| {
+                let (sx0, sy0, sx1, sy1) = shape.bounds();
+                !shape.locked
+                    && !shape.hidden
+                    && sx0 <= max_x
+                    && sx1 >= min_x
+                    && sy0 <= max_y
+                    && sy1 >= min_y
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let mut selection = if extend {
+            imp.selection.borrow().clone()
+        } else {
+            Vec::new()
+        };
+        for idx in hits {
+            selection.extend(self.group_members(idx));
+        }
+        selection.sort_unstable();
+        selection.dedup();
+
+        *imp.selection.borrow_mut() = selection;
+    }
+
+    pub fn get_image_data(&self) -> Option<Vec<u8>> {
+        let imp = self.imp();
+        let image = imp.render_cache.borrow().flatten(&imp.shapes.borrow())?;
 
         let mut data = Vec::new();
-        surface.write_to_png(&mut data).ok()?;
+        image.write_to_png(&mut data).ok()?;
 
         Some(data)
     }
@@ -863,43 +2196,174 @@ impl EditorCanvas {
     }
 
     pub fn zoom_in(&self) {
-        let imp = self.imp();
-        let current = imp.zoom.get();
-        let new_zoom = (current * 1.25).min(5.0);
-        imp.zoom.set(new_zoom);
-        self.update_content_size();
-        self.queue_draw();
+        self.set_zoom(self.imp().zoom.get() * ZOOM_STEP);
     }
 
     pub fn zoom_out(&self) {
-        let imp = self.imp();
-        let current = imp.zoom.get();
-        let new_zoom = (current / 1.25).max(0.1);
-        imp.zoom.set(new_zoom);
-        self.update_content_size();
-        self.queue_draw();
+        self.set_zoom(self.imp().zoom.get() / ZOOM_STEP);
     }
 
     pub fn zoom_reset(&self) {
-        self.imp().zoom.set(1.0);
+        self.set_zoom(1.0);
+    }
+
+    /// Zooms around the middle of the visible area.
+    pub fn set_zoom(&self, zoom: f64) {
+        let (x, y) = self.view_center();
+        self.zoom_at(zoom, x, y);
+    }
+
+    /// Zooms keeping the image point at (`x`, `y`), in widget coordinates,
+    /// at the same place on screen.
+    pub fn zoom_at(&self, zoom: f64, x: f64, y: f64) {
+        self.imp().fit.set(None);
+        self.apply_zoom(zoom, x, y);
+    }
+
+    /// Zooms so the whole image is visible, and keeps it fitted as the
+    /// window resizes.
+    pub fn zoom_to_fit(&self) {
+        self.fit_view(FitMode::Window);
+    }
+
+    /// Zooms so the image fills the width of the visible area.
+    pub fn zoom_to_fit_width(&self) {
+        self.fit_view(FitMode::Width);
+    }
+
+    fn fit_view(&self, mode: FitMode) {
+        self.imp().fit.set(Some(mode));
+        self.apply_fit();
+    }
+
+    fn apply_fit(&self) {
+        let Some(mode) = self.imp().fit.get() else {
+            return;
+        };
+        let (Some((width, height)), Some(scrolled)) = (self.image_size(), self.scrolled_window())
+        else {
+            return;
+        };
+        let (view_width, view_height) = (scrolled.width() as f64, scrolled.height() as f64);
+        if view_width <= 0.0 || view_height <= 0.0 {
+            return;
+        }
+
+        let width_zoom = view_width / width as f64;
+        let whole_zoom = width_zoom.min(view_height / height as f64);
+        let zoom = match mode {
+            FitMode::Window => whole_zoom,
+            FitMode::Width => width_zoom,
+            FitMode::Shrink => whole_zoom.min(1.0),
+        };
+
+        let (x, y) = self.view_center();
+        self.apply_zoom(zoom, x, y);
+    }
+
+    fn apply_zoom(&self, zoom: f64, x: f64, y: f64) {
+        let imp = self.imp();
+        let previous = imp.zoom.get();
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if zoom == previous {
+            return;
+        }
+
+        imp.zoom.set(zoom);
         self.update_content_size();
+
+        if let (Some(scrolled), Some((width, height))) = (self.scrolled_window(), self.image_size())
+        {
+            let ratio = zoom / previous;
+            Self::scroll_anchor(&scrolled.hadjustment(), x, ratio, width as f64 * zoom);
+            Self::scroll_anchor(&scrolled.vadjustment(), y, ratio, height as f64 * zoom);
+        }
+
         self.queue_draw();
+        self.emit_view_changed();
     }
 
-    pub fn pan(&self, delta_x: f64, delta_y: f64) {
-        if let Some(parent) = self.parent() {
-            if let Some(scrolled) = parent.parent() {
-                if let Ok(scrolled_window) = scrolled.downcast::<gtk4::ScrolledWindow>() {
-                    let h_adj = scrolled_window.hadjustment();
-                    let v_adj = scrolled_window.vadjustment();
+    /// Scrolls so the content at `anchor` stays at the same place in the
+    /// view after growing by `ratio` to `extent`. The viewport only updates
+    /// the adjustment on its next allocation, so the new range is set here.
+    fn scroll_anchor(adjustment: &gtk4::Adjustment, anchor: f64, ratio: f64, extent: f64) {
+        let page = adjustment.page_size();
+        let upper = extent.max(page);
+        let in_view = anchor - adjustment.value();
+        let value = (anchor * ratio - in_view).clamp(0.0, upper - page);
+        adjustment.configure(
+            value,
+            0.0,
+            upper,
+            adjustment.step_increment(),
+            adjustment.page_increment(),
+            page,
+        );
+    }
 
-                    let new_h = h_adj.value() + delta_x;
-                    let new_v = v_adj.value() + delta_y;
+    /// Part of the canvas inside the scrolled window, as `(x, y, width,
+    /// height)` in widget coordinates.
+    fn visible_area(&self) -> Option<(f64, f64, f64, f64)> {
+        let scrolled = self.scrolled_window()?;
+        let (h_adj, v_adj) = (scrolled.hadjustment(), scrolled.vadjustment());
+        (h_adj.page_size() > 0.0 && v_adj.page_size() > 0.0).then(|| {
+            (
+                h_adj.value(),
+                v_adj.value(),
+                h_adj.page_size(),
+                v_adj.page_size(),
+            )
+        })
+    }
 
-                    h_adj.set_value(new_h.clamp(h_adj.lower(), h_adj.upper() - h_adj.page_size()));
-                    v_adj.set_value(new_v.clamp(v_adj.lower(), v_adj.upper() - v_adj.page_size()));
-                }
+    /// Middle of the visible area, in widget coordinates.
+    fn view_center(&self) -> (f64, f64) {
+        match self.scrolled_window() {
+            Some(scrolled) => {
+                let (h_adj, v_adj) = (scrolled.hadjustment(), scrolled.vadjustment());
+                (
+                    h_adj.value() + h_adj.page_size() / 2.0,
+                    v_adj.value() + v_adj.page_size() / 2.0,
+                )
             }
+            None => (0.0, 0.0),
+        }
+    }
+
+    /// The scrolled window around the canvas's viewport.
+    fn scrolled_window(&self) -> Option<gtk4::ScrolledWindow> {
+        self.parent()?.parent()?.downcast().ok()
+    }
+
+    fn begin_pan(&self) {
+        let Some(scrolled) = self.scrolled_window() else {
+            return;
+        };
+        let imp = self.imp();
+        imp.panning.set(true);
+        imp.pan_start_x.set(scrolled.hadjustment().value());
+        imp.pan_start_y.set(scrolled.vadjustment().value());
+    }
+
+    /// Scrolls so the point grabbed when the pan began stays under the
+    /// pointer. Drag offsets are in widget coordinates, which move along
+    /// with the scrolling, so the scrolling done so far is taken out first.
+    fn pan_drag(&self, offset_x: f64, offset_y: f64) {
+        let Some(scrolled) = self.scrolled_window() else {
+            return;
+        };
+        let imp = self.imp();
+
+        for (adjustment, start, offset) in [
+            (scrolled.hadjustment(), imp.pan_start_x.get(), offset_x),
+            (scrolled.vadjustment(), imp.pan_start_y.get(), offset_y),
+        ] {
+            let screen_offset = offset - (adjustment.value() - start);
+            let value = start - screen_offset;
+            adjustment.set_value(value.clamp(
+                adjustment.lower(),
+                adjustment.upper() - adjustment.page_size(),
+            ));
         }
     }
 
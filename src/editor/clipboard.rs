@@ -0,0 +1,62 @@
+use gtk4::gdk;
+use gtk4::gdk_pixbuf::Pixbuf;
+use gtk4::gio;
+use gtk4::glib;
+use gtk4::prelude::*;
+
+use super::shapes::Shape;
+
+/// Private clipboard format for copied shapes, so they can be pasted into
+/// another niri-shot window.
+pub const SHAPES_MIME: &str = "application/x-niri-shot-shapes";
+
+const PNG_MIME: &str = "image/png";
+
+pub fn write_shapes(clipboard: &gdk::Clipboard, shapes: &[Shape]) -> bool {
+    let Ok(json) = serde_json::to_vec(shapes) else {
+        return false;
+    };
+
+    let provider = gdk::ContentProvider::for_bytes(SHAPES_MIME, &glib::Bytes::from_owned(json));
+    clipboard.set_content(Some(&provider)).is_ok()
+}
+
+pub async fn read_shapes(clipboard: &gdk::Clipboard) -> Option<Vec<Shape>> {
+    if !clipboard.formats().contain_mime_type(SHAPES_MIME) {
+        return None;
+    }
+
+    let bytes = read_bytes(clipboard, SHAPES_MIME).await?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Reads a PNG image from the clipboard, such as a screenshot copied from
+/// another program.
+pub async fn read_image(clipboard: &gdk::Clipboard) -> Option<Pixbuf> {
+    if !clipboard.formats().contain_mime_type(PNG_MIME) {
+        return None;
+    }
+
+    let bytes = read_bytes(clipboard, PNG_MIME).await?;
+    let stream = gio::MemoryInputStream::from_bytes(&bytes);
+    Pixbuf::from_stream(&stream, gio::Cancellable::NONE).ok()
+}
+
+pub async fn read_bytes(clipboard: &gdk::Clipboard, mime_type: &str) -> Option<glib::Bytes> {
+    let (stream, _) = clipboard
+        .read_future(&[mime_type], glib::Priority::DEFAULT)
+        .await
+        .ok()?;
+
+    let output = gio::MemoryOutputStream::new_resizable();
+    output
+        .splice_future(
+            &stream,
+            gio::OutputStreamSpliceFlags::CLOSE_SOURCE | gio::OutputStreamSpliceFlags::CLOSE_TARGET,
+            glib::Priority::DEFAULT,
+        )
+        .await
+        .ok()?;
+
+    Some(output.steal_as_bytes())
+}
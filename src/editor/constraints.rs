@@ -0,0 +1,97 @@
+use std::f64::consts::PI;
+
+pub const ANGLE_STEP: f64 = PI / 12.0;
+
+/// Moves `(x, y)` so the box spanned from `(ox, oy)` becomes a square.
+pub fn constrain_square(ox: f64, oy: f64, x: f64, y: f64) -> (f64, f64) {
+    constrain_aspect(ox, oy, x, y, 1.0)
+}
+
+/// Moves `(x, y)` so the box spanned from `(ox, oy)` keeps `aspect`
+/// (width / height), growing whichever side is shorter.
+pub fn constrain_aspect(ox: f64, oy: f64, x: f64, y: f64, aspect: f64) -> (f64, f64) {
+    if aspect <= 0.0 || !aspect.is_finite() {
+        return (x, y);
+    }
+
+    let dx = x - ox;
+    let dy = y - oy;
+    let mut width = dx.abs();
+    let mut height = dy.abs();
+
+    if width > height * aspect {
+        height = width / aspect;
+    } else {
+        width = height * aspect;
+    }
+
+    (
+        ox + width * if dx < 0.0 { -1.0 } else { 1.0 },
+        oy + height * if dy < 0.0 { -1.0 } else { 1.0 },
+    )
+}
+
+/// Rotates `(x, y)` around `(ox, oy)` onto the nearest `ANGLE_STEP`
+/// direction, keeping its distance.
+pub fn snap_direction(ox: f64, oy: f64, x: f64, y: f64) -> (f64, f64) {
+    let dx = x - ox;
+    let dy = y - oy;
+    let length = (dx * dx + dy * dy).sqrt();
+    let angle = snap_angle(dy.atan2(dx));
+
+    (ox + length * angle.cos(), oy + length * angle.sin())
+}
+
+pub fn snap_angle(angle: f64) -> f64 {
+    (angle / ANGLE_STEP).round() * ANGLE_STEP
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close((x, y): (f64, f64), (ex, ey): (f64, f64)) {
+        assert!(
+            (x - ex).abs() < 1e-9 && (y - ey).abs() < 1e-9,
+            "({}, {}) != ({}, {})",
+            x,
+            y,
+            ex,
+            ey
+        );
+    }
+
+    #[test]
+    fn snap_angle_rounds_to_15_degrees() {
+        assert_eq!(snap_angle(0.0), 0.0);
+        assert!((snap_angle(20f64.to_radians()) - 15f64.to_radians()).abs() < 1e-9);
+        assert!((snap_angle(-50f64.to_radians()) + 45f64.to_radians()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn snap_direction_keeps_the_length() {
+        assert_close(
+            snap_direction(10.0, 10.0, 20.0, 10.5),
+            (10.0 + 100.25f64.sqrt(), 10.0),
+        );
+        assert_close(snap_direction(0.0, 0.0, 0.0, -4.0), (0.0, -4.0));
+    }
+
+    #[test]
+    fn constrain_aspect_grows_the_shorter_side() {
+        assert_close(constrain_aspect(0.0, 0.0, 40.0, 10.0, 2.0), (40.0, 20.0));
+        assert_close(constrain_aspect(0.0, 0.0, 10.0, 30.0, 2.0), (60.0, 30.0));
+    }
+
+    #[test]
+    fn constrain_aspect_keeps_the_drag_direction() {
+        assert_close(constrain_square(10.0, 10.0, 0.0, 15.0), (0.0, 20.0));
+        assert_close(constrain_square(10.0, 10.0, 5.0, -10.0), (-10.0, -10.0));
+    }
+
+    #[test]
+    fn constrain_aspect_ignores_invalid_aspects() {
+        assert_eq!(constrain_aspect(0.0, 0.0, 4.0, 3.0, 0.0), (4.0, 3.0));
+        assert_eq!(constrain_aspect(0.0, 0.0, 4.0, 3.0, f64::NAN), (4.0, 3.0));
+    }
+}
@@ -0,0 +1,144 @@
+use super::shapes::Shape;
+
+const MAX_DEPTH: usize = 100;
+
+/// Snapshot-based undo history for the shape list.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Vec<Shape>>,
+    redo: Vec<Vec<Shape>>,
+    merge_key: Option<(&'static str, Vec<usize>)>,
+}
+
+impl History {
+    /// Stores `snapshot`, the shapes as they were before an edit.
+    pub fn record(&mut self, snapshot: Vec<Shape>) {
+        self.push(snapshot);
+        self.merge_key = None;
+    }
+
+    /// Like `record`, but consecutive edits with the same key on the same
+    /// selection collapse into one step, so dragging a slider or picking
+    /// colors is undone in one go.
+    pub fn record_merged(&mut self, key: &'static str, selection: &[usize], snapshot: Vec<Shape>) {
+        let merge_key = Some((key, selection.to_vec()));
+        if self.merge_key == merge_key {
+            return;
+        }
+
+        self.push(snapshot);
+        self.merge_key = merge_key;
+    }
+
+    /// Ends the current run of merged edits, so the next one gets its own
+    /// undo step even if it has the same key and selection.
+    pub fn break_merge(&mut self) {
+        self.merge_key = None;
+    }
+
+    fn push(&mut self, snapshot: Vec<Shape>) {
+        self.undo.push(snapshot);
+        if self.undo.len() > MAX_DEPTH {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Returns the shapes to restore, keeping `current` for redo.
+    pub fn undo(&mut self, current: Vec<Shape>) -> Option<Vec<Shape>> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        self.merge_key = None;
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: Vec<Shape>) -> Option<Vec<Shape>> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        self.merge_key = None;
+        Some(next)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.merge_key = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shapes(count: usize) -> Vec<Shape> {
+        vec![Shape::default(); count]
+    }
+
+    #[test]
+    fn undo_and_redo_swap_snapshots() {
+        let mut history = History::default();
+        history.record(shapes(0));
+
+        assert_eq!(history.undo(shapes(1)), Some(shapes(0)));
+        assert_eq!(history.undo(shapes(0)), None);
+        assert_eq!(history.redo(shapes(0)), Some(shapes(1)));
+        assert_eq!(history.redo(shapes(1)), None);
+    }
+
+    #[test]
+    fn recording_clears_redo() {
+        let mut history = History::default();
+        history.record(shapes(0));
+        history.undo(shapes(1));
+        history.record(shapes(0));
+
+        assert_eq!(history.redo(shapes(2)), None);
+    }
+
+    #[test]
+    fn keeps_at_most_max_depth_steps() {
+        let mut history = History::default();
+        for count in 0..MAX_DEPTH + 5 {
+            history.record(shapes(count));
+        }
+
+        let mut undone = 0;
+        while history.undo(Vec::new()).is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_DEPTH);
+    }
+
+    #[test]
+    fn merges_edits_with_the_same_key_and_selection() {
+        let mut history = History::default();
+        history.record_merged("color", &[0], shapes(1));
+        history.record_merged("color", &[0], shapes(2));
+
+        assert_eq!(history.undo(Vec::new()), Some(shapes(1)));
+        assert_eq!(history.undo(Vec::new()), None);
+    }
+
+    #[test]
+    fn does_not_merge_different_keys_or_selections() {
+        let mut history = History::default();
+        history.record_merged("color", &[0], shapes(1));
+        history.record_merged("stroke_width", &[0], shapes(2));
+        history.record_merged("stroke_width", &[1], shapes(3));
+
+        assert_eq!(history.undo(Vec::new()), Some(shapes(3)));
+        assert_eq!(history.undo(Vec::new()), Some(shapes(2)));
+        assert_eq!(history.undo(Vec::new()), Some(shapes(1)));
+    }
+
+    #[test]
+    fn break_merge_starts_a_new_step() {
+        let mut history = History::default();
+        history.record_merged("color", &[0], shapes(1));
+        history.break_merge();
+        history.record_merged("color", &[0], shapes(2));
+
+        assert_eq!(history.undo(Vec::new()), Some(shapes(2)));
+        assert_eq!(history.undo(Vec::new()), Some(shapes(1)));
+    }
+}
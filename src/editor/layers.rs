@@ -0,0 +1,291 @@
+use gtk4::prelude::*;
+use gtk4::{
+    gdk, glib, Box, Button, DragSource, DrawingArea, DropTarget, EditableLabel, Label, ListBox,
+    ListBoxRow, Orientation, ScrolledWindow, SelectionMode, ToggleButton,
+};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use super::arrange::ZOrder;
+use super::canvas::EditorCanvas;
+use super::shapes::{Shape, ShapeType};
+
+/// Side panel listing every shape, topmost first.
+pub struct LayersPanel {
+    root: Box,
+    list: ListBox,
+    canvas: EditorCanvas,
+    syncing: Cell<bool>,
+    rebuild_pending: Cell<bool>,
+}
+
+impl LayersPanel {
+    pub fn new(canvas: &EditorCanvas) -> Rc<Self> {
+        let root = Box::new(Orientation::Vertical, 6);
+        root.add_css_class("layers-panel");
+        root.set_width_request(240);
+
+        let title = Label::new(Some("Layers"));
+        title.set_halign(gtk4::Align::Start);
+        title.add_css_class("heading");
+
+        let list = ListBox::new();
+        list.set_selection_mode(SelectionMode::Multiple);
+
+        let scrolled = ScrolledWindow::new();
+        scrolled.set_child(Some(&list));
+        scrolled.set_vexpand(true);
+
+        root.append(&title);
+        root.append(&scrolled);
+        root.append(&Self::create_order_bar(canvas));
+
+        let panel = Rc::new(Self {
+            root,
+            list,
+            canvas: canvas.clone(),
+            syncing: Cell::new(false),
+            rebuild_pending: Cell::new(false),
+        });
+
+        let weak = Rc::downgrade(&panel);
+        panel.list.connect_selected_rows_changed(move |list| {
+            let Some(panel) = weak.upgrade() else {
+                return;
+            };
+            if panel.syncing.get() {
+                return;
+            }
+            let selection = list
+                .selected_rows()
+                .iter()
+                .filter_map(|row| panel.shape_index(row))
+                .collect();
+            panel.canvas.set_selection(selection);
+        });
+
+        let weak = Rc::downgrade(&panel);
+        canvas.connect_shapes_changed(move |_| {
+            if let Some(panel) = weak.upgrade() {
+                panel.schedule_rebuild();
+            }
+        });
+
+        let weak = Rc::downgrade(&panel);
+        canvas.connect_selection_changed(move |_| {
+            if let Some(panel) = weak.upgrade() {
+                panel.sync_selection();
+            }
+        });
+
+        panel.rebuild();
+        panel
+    }
+
+    pub fn widget(&self) -> &Box {
+        &self.root
+    }
+
+    fn create_order_bar(canvas: &EditorCanvas) -> Box {
+        let bar = Box::new(Orientation::Horizontal, 4);
+        bar.set_halign(gtk4::Align::Center);
+
+        let actions = [
+            (
+                "go-top-symbolic",
+                "Bring to Front (Ctrl+Shift+])",
+                ZOrder::Front,
+            ),
+            ("go-up-symbolic", "Bring Forward (Ctrl+])", ZOrder::Forward),
+            (
+                "go-down-symbolic",
+                "Send Backward (Ctrl+[)",
+                ZOrder::Backward,
+            ),
+            (
+                "go-bottom-symbolic",
+                "Send to Back (Ctrl+Shift+[)",
+                ZOrder::Back,
+            ),
+        ];
+
+        for (icon, tooltip, order) in actions {
+            let btn = Button::from_icon_name(icon);
+            btn.set_tooltip_text(Some(tooltip));
+            let canvas = canvas.clone();
+            btn.connect_clicked(move |_| canvas.reorder_selected(order));
+            bar.append(&btn);
+        }
+
+        bar
+    }
+
+    /// Rebuilds on idle so edits made from inside a row's own signal handler
+    /// don't destroy that row mid-emission.
+    fn schedule_rebuild(self: &Rc<Self>) {
+        if self.rebuild_pending.replace(true) {
+            return;
+        }
+
+        let weak = Rc::downgrade(self);
+        glib::idle_add_local_once(move || {
+            if let Some(panel) = weak.upgrade() {
+                panel.rebuild_pending.set(false);
+                panel.rebuild();
+            }
+        });
+    }
+
+    fn rebuild(self: &Rc<Self>) {
+        self.syncing.set(true);
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+
+        for (idx, shape) in self.canvas.shapes().iter().enumerate().rev() {
+            self.list.append(&self.create_row(idx, shape));
+        }
+
+        self.syncing.set(false);
+        self.sync_selection();
+    }
+
+    fn sync_selection(&self) {
+        self.syncing.set(true);
+        let selection = self.canvas.selection();
+
+        let mut child = self.list.first_child();
+        while let Some(widget) = child {
+            if let Ok(row) = widget.clone().downcast::<ListBoxRow>() {
+                let selected = self
+                    .shape_index(&row)
+                    .is_some_and(|idx| selection.contains(&idx));
+                if selected {
+                    self.list.select_row(Some(&row));
+                } else {
+                    self.list.unselect_row(&row);
+                }
+            }
+            child = widget.next_sibling();
+        }
+
+        self.syncing.set(false);
+    }
+
+    fn shape_index(&self, row: &ListBoxRow) -> Option<usize> {
+        let count = self.canvas.shape_count();
+        let position = usize::try_from(row.index()).ok()?;
+        (position < count).then(|| count - 1 - position)
+    }
+
+    fn create_row(self: &Rc<Self>, idx: usize, shape: &Shape) -> ListBoxRow {
+        let line = Box::new(Orientation::Horizontal, 6);
+        line.set_margin_top(2);
+        line.set_margin_bottom(2);
+        line.set_margin_start(4);
+        line.set_margin_end(4);
+
+        let icon = Label::new(Some(Self::icon(shape.shape_type)));
+        icon.add_css_class("nerd-icon");
+
+        let color = shape.color;
+        let swatch = DrawingArea::new();
+        swatch.set_content_width(14);
+        swatch.set_content_height(14);
+        swatch.set_valign(gtk4::Align::Center);
+        swatch.set_draw_func(move |_, ctx, width, height| {
+            ctx.set_source_rgba(color.r, color.g, color.b, color.a);
+            ctx.rectangle(0.0, 0.0, width as f64, height as f64);
+            let _ = ctx.fill();
+        });
+
+        let current = Self::label(shape);
+        let label = EditableLabel::new(&current);
+        label.set_hexpand(true);
+        let canvas = self.canvas.clone();
+        label.connect_editing_notify(move |label| {
+            if !label.is_editing() && label.text() != current {
+                canvas.rename_shape(idx, &label.text());
+            }
+        });
+
+        let visible = ToggleButton::new();
+        visible.set_icon_name(if shape.hidden {
+            "view-conceal-symbolic"
+        } else {
+            "view-reveal-symbolic"
+        });
+        visible.set_active(!shape.hidden);
+        visible.set_tooltip_text(Some("Show/Hide"));
+        let canvas = self.canvas.clone();
+        visible.connect_toggled(move |btn| canvas.set_shape_hidden(idx, !btn.is_active()));
+
+        let lock = ToggleButton::new();
+        lock.set_icon_name(if shape.locked {
+            "changes-prevent-symbolic"
+        } else {
+            "changes-allow-symbolic"
+        });
+        lock.set_active(shape.locked);
+        lock.set_tooltip_text(Some("Lock/Unlock"));
+        let canvas = self.canvas.clone();
+        lock.connect_toggled(move |btn| canvas.set_shape_locked(idx, btn.is_active()));
+
+        line.append(&icon);
+        line.append(&swatch);
+        line.append(&label);
+        line.append(&visible);
+        line.append(&lock);
+
+        let row = ListBoxRow::new();
+        row.set_child(Some(&line));
+
+        let source = DragSource::new();
+        source.set_actions(gdk::DragAction::MOVE);
+        source.connect_prepare(move |_, _, _| {
+            Some(gdk::ContentProvider::for_value(&(idx as u32).to_value()))
+        });
+        row.add_controller(source);
+
+        let target = DropTarget::new(u32::static_type(), gdk::DragAction::MOVE);
+        let canvas = self.canvas.clone();
+        target.connect_drop(move |_, value, _, _| match value.get::<u32>() {
+            Ok(from) => {
+                canvas.move_shape(from as usize, idx);
+                true
+            }
+            Err(_) => false,
+        });
+        row.add_controller(target);
+
+        row
+    }
+
+    fn icon(shape_type: ShapeType) -> &'static str {
+        match shape_type {
+            ShapeType::Rectangle => "□",
+            ShapeType::Ellipse => "○",
+            ShapeType::Line => "╱",
+            ShapeType::Arrow => "󰁕",
+            ShapeType::FreeHand => "󰏬",
+            ShapeType::Text => "󰊄",
+            ShapeType::Blur => "󰂵",
+            ShapeType::Highlight => "󰸱",
+            ShapeType::Marker => "󰙒",
+            ShapeType::Image => "󰋩",
+        }
+    }
+
+    fn label(shape: &Shape) -> String {
+        if !shape.name.is_empty() {
+            return shape.name.clone();
+        }
+
+        if shape.shape_type == ShapeType::Text {
+            let preview: String = shape.text.chars().take(24).collect();
+            return format!("\u{201c}{}\u{201d}", preview);
+        }
+
+        format!("{:?}", shape.shape_type)
+    }
+}
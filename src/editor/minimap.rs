@@ -0,0 +1,199 @@
+use gtk4::gdk_pixbuf::{InterpType, Pixbuf};
+use gtk4::prelude::*;
+use gtk4::{DrawingArea, GestureDrag, ScrolledWindow};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use super::canvas::EditorCanvas;
+
+const MAX_WIDTH: f64 = 200.0;
+const MAX_HEIGHT: f64 = 150.0;
+
+/// Thumbnail of the whole screenshot in a corner of the canvas, with the
+/// visible part outlined. Clicking or dragging in it scrolls the view there.
+/// It only shows while part of the image is scrolled out of view.
+pub struct Minimap {
+    area: DrawingArea,
+    canvas: EditorCanvas,
+    scrolled: ScrolledWindow,
+    /// The image the thumbnail was made from, and the thumbnail.
+    thumbnail: RefCell<Option<(Pixbuf, Pixbuf)>>,
+    enabled: Cell<bool>,
+}
+
+impl Minimap {
+    pub fn new(canvas: &EditorCanvas, scrolled: &ScrolledWindow) -> Rc<Self> {
+        let area = DrawingArea::new();
+        area.add_css_class("minimap");
+        area.set_halign(gtk4::Align::End);
+        area.set_valign(gtk4::Align::End);
+        area.set_margin_end(12);
+        area.set_margin_bottom(12);
+        area.set_cursor_from_name(Some("move"));
+        area.set_visible(false);
+
+        let minimap = Rc::new(Self {
+            area,
+            canvas: canvas.clone(),
+            scrolled: scrolled.clone(),
+            thumbnail: RefCell::new(None),
+            enabled: Cell::new(true),
+        });
+
+        let weak = Rc::downgrade(&minimap);
+        minimap.area.set_draw_func(move |_, ctx, _, _| {
+            if let Some(minimap) = weak.upgrade() {
+                minimap.draw(ctx);
+            }
+        });
+
+        let weak = Rc::downgrade(&minimap);
+        canvas.connect_view_changed(move |_| {
+            if let Some(minimap) = weak.upgrade() {
+                minimap.sync();
+            }
+        });
+
+        for adjustment in [scrolled.hadjustment(), scrolled.vadjustment()] {
+            let weak = Rc::downgrade(&minimap);
+            adjustment.connect_changed(move |_| {
+                if let Some(minimap) = weak.upgrade() {
+                    minimap.sync();
+                }
+            });
+
+            let weak = Rc::downgrade(&minimap);
+            adjustment.connect_value_changed(move |_| {
+                if let Some(minimap) = weak.upgrade() {
+                    minimap.area.queue_draw();
+                }
+            });
+        }
+
+        let drag = GestureDrag::new();
+
+        let weak = Rc::downgrade(&minimap);
+        drag.connect_drag_begin(move |_, x, y| {
+            if let Some(minimap) = weak.upgrade() {
+                minimap.scroll_to(x, y);
+            }
+        });
+
+        let weak = Rc::downgrade(&minimap);
+        drag.connect_drag_update(move |gesture, offset_x, offset_y| {
+            if let (Some(minimap), Some((x, y))) = (weak.upgrade(), gesture.start_point()) {
+                minimap.scroll_to(x + offset_x, y + offset_y);
+            }
+        });
+
+        minimap.area.add_controller(drag);
+
+        minimap
+    }
+
+    pub fn widget(&self) -> &DrawingArea {
+        &self.area
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.set(enabled);
+        self.sync();
+    }
+
+    /// Remakes the thumbnail when the image changed, and shows the minimap
+    /// when it is enabled and the image doesn't fit in the view.
+    fn sync(&self) {
+        let image = self.canvas.image();
+
+        let stale = match (&image, &*self.thumbnail.borrow()) {
+            (Some(image), Some((source, _))) => image != source,
+            (None, None) => false,
+            _ => true,
+        };
+        if stale {
+            *self.thumbnail.borrow_mut() = image.as_ref().and_then(|image| {
+                let scale = (MAX_WIDTH / image.width() as f64)
+                    .min(MAX_HEIGHT / image.height() as f64)
+                    .min(1.0);
+                let width = ((image.width() as f64 * scale).round() as i32).max(1);
+                let height = ((image.height() as f64 * scale).round() as i32).max(1);
+                let thumbnail = image.scale_simple(width, height, InterpType::Bilinear)?;
+                Some((image.clone(), thumbnail))
+            });
+        }
+
+        let thumbnail = self.thumbnail.borrow();
+        if let Some((_, thumbnail)) = &*thumbnail {
+            self.area.set_content_width(thumbnail.width());
+            self.area.set_content_height(thumbnail.height());
+        }
+
+        let (h_adj, v_adj) = (self.scrolled.hadjustment(), self.scrolled.vadjustment());
+        let scrolls =
+            h_adj.upper() > h_adj.page_size() + 1.0 || v_adj.upper() > v_adj.page_size() + 1.0;
+        self.area
+            .set_visible(self.enabled.get() && thumbnail.is_some() && scrolls);
+        self.area.queue_draw();
+    }
+
+    /// Thumbnail pixels per canvas widget pixel.
+    fn scale(&self) -> Option<f64> {
+        let thumbnail = self.thumbnail.borrow();
+        let (image, thumbnail) = thumbnail.as_ref()?;
+        Some(thumbnail.width() as f64 / (image.width() as f64 * self.canvas.get_zoom()))
+    }
+
+    fn draw(&self, ctx: &cairo::Context) {
+        let Some(scale) = self.scale() else {
+            return;
+        };
+        let thumbnail = self.thumbnail.borrow();
+        let Some((_, thumbnail)) = &*thumbnail else {
+            return;
+        };
+
+        ctx.set_source_pixbuf(thumbnail, 0.0, 0.0);
+        let _ = ctx.paint();
+
+        let (h_adj, v_adj) = (self.scrolled.hadjustment(), self.scrolled.vadjustment());
+        let x = h_adj.value() * scale;
+        let y = v_adj.value() * scale;
+        let width = h_adj.page_size() * scale;
+        let height = v_adj.page_size() * scale;
+
+        // Dim what is out of view.
+        ctx.set_fill_rule(cairo::FillRule::EvenOdd);
+        ctx.rectangle(
+            0.0,
+            0.0,
+            thumbnail.width() as f64,
+            thumbnail.height() as f64,
+        );
+        ctx.rectangle(x, y, width, height);
+        ctx.set_source_rgba(0.0, 0.0, 0.0, 0.4);
+        let _ = ctx.fill();
+
+        ctx.rectangle(x + 0.5, y + 0.5, width - 1.0, height - 1.0);
+        ctx.set_source_rgba(0.2, 0.5, 1.0, 0.9);
+        ctx.set_line_width(1.0);
+        let _ = ctx.stroke();
+    }
+
+    /// Scrolls so the view is centered on thumbnail point (`x`, `y`).
+    fn scroll_to(&self, x: f64, y: f64) {
+        let Some(scale) = self.scale() else {
+            return;
+        };
+
+        for (adjustment, position) in [
+            (self.scrolled.hadjustment(), x),
+            (self.scrolled.vadjustment(), y),
+        ] {
+            let value = position / scale - adjustment.page_size() / 2.0;
+            adjustment.set_value(value.clamp(
+                adjustment.lower(),
+                adjustment.upper() - adjustment.page_size(),
+            ));
+        }
+    }
+}
@@ -1,7 +1,25 @@
+mod arrange;
 mod canvas;
+mod clipboard;
+mod constraints;
+mod history;
+mod layers;
+mod minimap;
+mod properties;
+mod render;
 mod shapes;
+mod smoothing;
+mod snapping;
+mod status_bar;
+mod swatches;
 mod tools;
 
+pub use arrange::{Alignment, Distribution, ZOrder};
 pub use canvas::EditorCanvas;
-pub use shapes::Color;
-pub use tools::ToolType;
+pub use layers::LayersPanel;
+pub use minimap::Minimap;
+pub use properties::PropertiesPanel;
+pub use shapes::{Color, DashStyle, FillMode, StrokeCap, StrokeJoin};
+pub use status_bar::StatusBar;
+pub use swatches::ColorSwatches;
+pub use tools::{tool_stroke_width, ToolType};
@@ -0,0 +1,283 @@
+use gtk4::gdk::RGBA;
+use gtk4::prelude::*;
+use gtk4::{Box, ColorDialog, ColorDialogButton, DropDown, Label, Orientation, Scale, SpinButton};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use super::canvas::EditorCanvas;
+use super::shapes::{Color, FillMode, Shape, ShapeType};
+use super::tools::tool_stroke_width;
+
+/// Side panel editing the style and geometry of the selected shapes.
+/// Hidden while nothing is selected.
+pub struct PropertiesPanel {
+    root: Box,
+    canvas: EditorCanvas,
+    color: ColorDialogButton,
+    stroke_width: Scale,
+    fill: DropDown,
+    fill_color: ColorDialogButton,
+    font_size: SpinButton,
+    opacity: Scale,
+    x: SpinButton,
+    y: SpinButton,
+    width: SpinButton,
+    height: SpinButton,
+    rotation: SpinButton,
+    syncing: Cell<bool>,
+}
+
+impl PropertiesPanel {
+    pub fn new(canvas: &EditorCanvas) -> Rc<Self> {
+        let root = Box::new(Orientation::Vertical, 6);
+        root.add_css_class("properties-panel");
+        root.set_width_request(240);
+
+        let title = Label::new(Some("Properties"));
+        title.set_halign(gtk4::Align::Start);
+        title.add_css_class("heading");
+
+        let color = ColorDialogButton::new(Some(ColorDialog::new()));
+
+        let stroke_width = Scale::with_range(Orientation::Horizontal, 1.0, 20.0, 1.0);
+        stroke_width.set_hexpand(true);
+
+        let fill = DropDown::from_strings(&FillMode::ALL.map(FillMode::label));
+        fill.set_hexpand(true);
+
+        let fill_color = ColorDialogButton::new(Some(ColorDialog::new()));
+
+        let font_size = SpinButton::with_range(6.0, 200.0, 1.0);
+
+        let opacity = Scale::with_range(Orientation::Horizontal, 0.0, 1.0, 0.05);
+        opacity.set_hexpand(true);
+
+        let x = SpinButton::with_range(-100_000.0, 100_000.0, 1.0);
+        let y = SpinButton::with_range(-100_000.0, 100_000.0, 1.0);
+        let width = SpinButton::with_range(0.0, 100_000.0, 1.0);
+        let height = SpinButton::with_range(0.0, 100_000.0, 1.0);
+        let rotation = SpinButton::with_range(-180.0, 180.0, 1.0);
+        rotation.set_wrap(true);
+
+        let geometry_title = Label::new(Some("Geometry"));
+        geometry_title.set_halign(gtk4::Align::Start);
+        geometry_title.add_css_class("heading");
+
+        root.append(&title);
+        root.append(&Self::row("Color", &color));
+        root.append(&Self::row("Stroke", &stroke_width));
+        root.append(&Self::row("Fill", &fill));
+        root.append(&Self::row("Fill color", &fill_color));
+        root.append(&Self::row("Font size", &font_size));
+        root.append(&Self::row("Opacity", &opacity));
+        root.append(&geometry_title);
+        root.append(&Self::row("X", &x));
+        root.append(&Self::row("Y", &y));
+        root.append(&Self::row("Width", &width));
+        root.append(&Self::row("Height", &height));
+        root.append(&Self::row("Rotation", &rotation));
+
+        let panel = Rc::new(Self {
+            root,
+            canvas: canvas.clone(),
+            color,
+            stroke_width,
+            fill,
+            fill_color,
+            font_size,
+            opacity,
+            x,
+            y,
+            width,
+            height,
+            rotation,
+            syncing: Cell::new(false),
+        });
+
+        panel.connect_controls();
+
+        let weak = Rc::downgrade(&panel);
+        canvas.connect_selection_changed(move |_| {
+            if let Some(panel) = weak.upgrade() {
+                panel.sync();
+            }
+        });
+
+        let weak = Rc::downgrade(&panel);
+        canvas.connect_shapes_changed(move |_| {
+            if let Some(panel) = weak.upgrade() {
+                panel.sync();
+            }
+        });
+
+        panel.sync();
+        panel
+    }
+
+    pub fn widget(&self) -> &Box {
+        &self.root
+    }
+
+    fn row(title: &str, control: &impl IsA<gtk4::Widget>) -> Box {
+        let row = Box::new(Orientation::Horizontal, 6);
+        let label = Label::new(Some(title));
+        label.set_width_chars(9);
+        label.set_xalign(0.0);
+        row.append(&label);
+        row.append(control);
+        row
+    }
+
+    fn connect_controls(self: &Rc<Self>) {
+        self.connect_geometry();
+
+        let weak = Rc::downgrade(self);
+        self.color.connect_rgba_notify(move |button| {
+            if let Some(panel) = weak.upgrade().filter(|p| !p.syncing.get()) {
+                panel.canvas.set_color(to_color(&button.rgba()));
+            }
+        });
+
+        let weak = Rc::downgrade(self);
+        self.fill_color.connect_rgba_notify(move |button| {
+            if let Some(panel) = weak.upgrade().filter(|p| !p.syncing.get()) {
+                panel.canvas.set_fill_color(to_color(&button.rgba()));
+            }
+        });
+
+        let weak = Rc::downgrade(self);
+        self.stroke_width.connect_value_changed(move |scale| {
+            if let Some(panel) = weak.upgrade().filter(|p| !p.syncing.get()) {
+                panel.canvas.set_stroke_width(scale.value());
+            }
+        });
+
+        let weak = Rc::downgrade(self);
+        self.fill.connect_selected_notify(move |dropdown| {
+            let Some(panel) = weak.upgrade().filter(|p| !p.syncing.get()) else {
+                return;
+            };
+            if let Some(&fill) = FillMode::ALL.get(dropdown.selected() as usize) {
+                panel.canvas.set_fill_mode(fill);
+            }
+        });
+
+        let weak = Rc::downgrade(self);
+        self.font_size.connect_value_changed(move |spin| {
+            if let Some(panel) = weak.upgrade().filter(|p| !p.syncing.get()) {
+                panel.canvas.set_font_size(spin.value());
+            }
+        });
+
+        let weak = Rc::downgrade(self);
+        self.opacity.connect_value_changed(move |scale| {
+            if let Some(panel) = weak.upgrade().filter(|p| !p.syncing.get()) {
+                panel.canvas.set_opacity(scale.value());
+            }
+        });
+    }
+
+    fn connect_geometry(self: &Rc<Self>) {
+        for spin in [&self.x, &self.y] {
+            let weak = Rc::downgrade(self);
+            spin.connect_value_changed(move |_| {
+                if let Some(panel) = weak.upgrade().filter(|p| !p.syncing.get()) {
+                    panel
+                        .canvas
+                        .set_selection_position(panel.x.value(), panel.y.value());
+                }
+            });
+        }
+
+        for spin in [&self.width, &self.height] {
+            let weak = Rc::downgrade(self);
+            spin.connect_value_changed(move |_| {
+                if let Some(panel) = weak.upgrade().filter(|p| !p.syncing.get()) {
+                    panel
+                        .canvas
+                        .set_selection_size(panel.width.value(), panel.height.value());
+                }
+            });
+        }
+
+        let weak = Rc::downgrade(self);
+        self.rotation.connect_value_changed(move |spin| {
+            if let Some(panel) = weak.upgrade().filter(|p| !p.syncing.get()) {
+                panel.canvas.set_selection_rotation(spin.value());
+            }
+        });
+    }
+
+    /// Shows the values of the first selected shape and enables only the
+    /// controls that apply to the selection.
+    fn sync(&self) {
+        let shapes = self.canvas.shapes();
+        let selected: Vec<&Shape> = self
+            .canvas
+            .selection()
+            .iter()
+            .filter_map(|&idx| shapes.get(idx))
+            .collect();
+
+        let Some(&shape) = selected.first() else {
+            self.root.set_visible(false);
+            return;
+        };
+        let text = selected
+            .iter()
+            .find(|s| s.shape_type == ShapeType::Text)
+            .copied();
+        let fillable = selected.iter().any(|s| s.is_fillable());
+
+        self.syncing.set(true);
+
+        self.color.set_rgba(&to_rgba(shape.color));
+        self.stroke_width.set_value(tool_stroke_width(shape));
+        if let Some(position) = FillMode::ALL.iter().position(|&f| f == shape.fill) {
+            self.fill.set_selected(position as u32);
+        }
+        self.fill_color.set_rgba(&to_rgba(shape.fill_color));
+        self.fill.set_sensitive(fillable);
+        self.fill_color.set_sensitive(fillable);
+        self.font_size
+            .set_value(text.map_or(shape.font_size, |s| s.font_size));
+        self.font_size.set_sensitive(text.is_some());
+        self.opacity.set_value(shape.opacity);
+
+        if let Some((x, y, width, height)) = self.canvas.selection_geometry() {
+            self.x.set_value(x);
+            self.y.set_value(y);
+            // An axis a straight line has no extent on can't be scaled, so
+            // it shows 0 and is disabled; the other can't be shrunk to 0.
+            for (spin, value) in [(&self.width, width), (&self.height, height)] {
+                let degenerate = value <= 0.0;
+                spin.set_range(if degenerate { 0.0 } else { 1.0 }, 100_000.0);
+                spin.set_value(value);
+                spin.set_sensitive(!degenerate);
+            }
+        }
+        self.rotation
+            .set_value((shape.rotation.to_degrees() + 180.0).rem_euclid(360.0) - 180.0);
+
+        self.syncing.set(false);
+        self.root.set_visible(true);
+    }
+}
+
+fn to_color(rgba: &RGBA) -> Color {
+    Color::new(
+        rgba.red() as f64,
+        rgba.green() as f64,
+        rgba.blue() as f64,
+        rgba.alpha() as f64,
+    )
+}
+
+fn to_rgba(color: Color) -> RGBA {
+    RGBA::new(
+        color.r as f32,
+        color.g as f32,
+        color.b as f32,
+        color.a as f32,
+    )
+}
@@ -0,0 +1,193 @@
+use gtk4::gdk_pixbuf::Pixbuf;
+use gtk4::prelude::GdkCairoContextExt;
+
+use super::shapes::Shape;
+
+/// Largest layer kept, in pixels: an 8K screenshot at 100%. Zoomed in
+/// further than that, the shapes are drawn on every frame instead.
+const LAYER_MAX_PIXELS: f64 = 7680.0 * 4320.0;
+
+/// The screenshot as a Cairo surface, and a copy of it with the committed
+/// annotations drawn on at the scale it is shown, so a frame is a single
+/// paint plus whatever is being drawn or dragged rather than a pixbuf upload
+/// and every shape again.
+#[derive(Default)]
+pub struct RenderCache {
+    base: Option<cairo::ImageSurface>,
+    layer: Option<cairo::ImageSurface>,
+    layer_key: Option<LayerKey>,
+}
+
+/// What `layer` was drawn from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LayerKey {
+    generation: u64,
+    count: usize,
+    scale: f64,
+    nearest: bool,
+}
+
+impl RenderCache {
+    pub fn set_image(&mut self, pixbuf: Option<&Pixbuf>) {
+        self.base = pixbuf.and_then(image_surface);
+        self.layer = None;
+        self.layer_key = None;
+    }
+
+    pub fn base(&self) -> Option<&cairo::ImageSurface> {
+        self.base.as_ref()
+    }
+
+    /// The screenshot with `shapes` drawn on it, rendered at `scale` device
+    /// pixels per image pixel so the shapes stay vectors at any zoom; the
+    /// screenshot is scaled as sharp squares when `nearest` is set. The
+    /// surface has a matching device scale, so it is painted in image
+    /// coordinates.
+    ///
+    /// It is redrawn only when `generation`, which the caller bumps whenever
+    /// any shape changes, the number of shapes or the scale differ from the
+    /// last call. Returns `None` when there is no screenshot or the layer
+    /// would be larger than `LAYER_MAX_PIXELS`.
+    pub fn layer(
+        &mut self,
+        shapes: &[Shape],
+        generation: u64,
+        scale: f64,
+        nearest: bool,
+    ) -> Option<&cairo::ImageSurface> {
+        let base = self.base.as_ref()?;
+        let width = (base.width() as f64 * scale).ceil();
+        let height = (base.height() as f64 * scale).ceil();
+        if width * height > LAYER_MAX_PIXELS {
+            self.layer = None;
+            self.layer_key = None;
+            return None;
+        }
+
+        let key = LayerKey {
+            generation,
+            count: shapes.len(),
+            scale,
+            nearest,
+        };
+        if self.layer.is_none() || self.layer_key != Some(key) {
+            let (width, height) = (width as i32, height as i32);
+            let layer = self
+                .layer
+                .take()
+                .filter(|layer| layer.width() == width && layer.height() == height)
+                .or_else(|| {
+                    cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).ok()
+                })?;
+            layer.set_device_scale(scale, scale);
+            {
+                let ctx = cairo::Context::new(&layer).ok()?;
+                ctx.set_operator(cairo::Operator::Clear);
+                let _ = ctx.paint();
+                ctx.set_operator(cairo::Operator::Over);
+                paint_base(&ctx, base, nearest);
+                for shape in shapes {
+                    shape.draw(&ctx);
+                }
+            }
+            self.layer_key = Some(key);
+            self.layer = Some(layer);
+        }
+
+        self.layer.as_ref()
+    }
+
+    /// The screenshot with `shapes` drawn on it at its own size, for export.
+    pub fn flatten(&self, shapes: &[Shape]) -> Option<cairo::ImageSurface> {
+        let base = self.base.as_ref()?;
+        let surface =
+            cairo::ImageSurface::create(cairo::Format::ARgb32, base.width(), base.height()).ok()?;
+        {
+            let ctx = cairo::Context::new(&surface).ok()?;
+            paint_base(&ctx, base, false);
+            for shape in shapes {
+                shape.draw(&ctx);
+            }
+        }
+        Some(surface)
+    }
+}
+
+/// Paints the screenshot at the image origin. With `nearest`, blown-up
+/// pixels stay sharp squares instead of being blurred.
+pub fn paint_base(ctx: &cairo::Context, base: &cairo::ImageSurface, nearest: bool) {
+    if ctx.set_source_surface(base, 0.0, 0.0).is_err() {
+        return;
+    }
+    if nearest {
+        ctx.source().set_filter(cairo::Filter::Nearest);
+    }
+    let _ = ctx.paint();
+}
+
+/// Copies `pixbuf` into a surface Cairo can paint without converting it.
+pub fn image_surface(pixbuf: &Pixbuf) -> Option<cairo::ImageSurface> {
+    let surface =
+        cairo::ImageSurface::create(cairo::Format::ARgb32, pixbuf.width(), pixbuf.height()).ok()?;
+    {
+        let ctx = cairo::Context::new(&surface).ok()?;
+        ctx.set_source_pixbuf(pixbuf, 0.0, 0.0);
+        ctx.paint().ok()?;
+    }
+    Some(surface)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(width: i32, height: i32) -> RenderCache {
+        RenderCache {
+            base: cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).ok(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn layer_is_rendered_at_the_display_scale() {
+        let mut cache = cache(40, 30);
+
+        let layer = cache.layer(&[], 0, 2.5, true).unwrap();
+        assert_eq!((layer.width(), layer.height()), (100, 75));
+        assert_eq!(layer.device_scale(), (2.5, 2.5));
+
+        let layer = cache.layer(&[], 0, 0.5, false).unwrap();
+        assert_eq!((layer.width(), layer.height()), (20, 15));
+    }
+
+    #[test]
+    fn layer_is_redrawn_only_when_its_key_changes() {
+        let mut cache = cache(10, 10);
+        let shapes = [Shape::default()];
+
+        cache.layer(&shapes, 1, 1.0, false);
+        assert_eq!(cache.layer_key.map(|key| key.count), Some(1));
+        cache.layer(&shapes[..0], 1, 1.0, false);
+        assert_eq!(cache.layer_key.map(|key| key.count), Some(0));
+        cache.layer(&shapes[..0], 2, 1.0, false);
+        assert_eq!(cache.layer_key.map(|key| key.generation), Some(2));
+    }
+
+    #[test]
+    fn oversized_layers_are_not_kept() {
+        let mut cache = cache(100, 100);
+
+        assert!(cache.layer(&[], 0, 1.0, false).is_some());
+        assert!(cache.layer(&[], 0, 1000.0, true).is_none());
+        assert!(cache.layer_key.is_none());
+    }
+
+    #[test]
+    fn flatten_keeps_the_image_size() {
+        let cache = cache(40, 30);
+        let image = cache.flatten(&[]).unwrap();
+
+        assert_eq!((image.width(), image.height()), (40, 30));
+        assert!(RenderCache::default().flatten(&[]).is_none());
+    }
+}
@@ -1,6 +1,11 @@
+use gtk4::gdk_pixbuf::Pixbuf;
+use gtk4::prelude::GdkCairoContextExt;
+use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
 
-#[derive(Debug, Clone, Copy)]
+use super::smoothing::{catmull_rom_path, segment_distance, simplify};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Color {
     pub r: f64,
     pub g: f64,
@@ -13,6 +18,18 @@ impl Color {
         Self { r, g, b, a }
     }
 
+    /// Red, green and blue as 0-255 channel values.
+    pub fn to_rgb8(self) -> (u8, u8, u8) {
+        let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        (channel(self.r), channel(self.g), channel(self.b))
+    }
+
+    /// `#rrggbb`, without alpha.
+    pub fn to_hex(self) -> String {
+        let (r, g, b) = self.to_rgb8();
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+
     #[allow(dead_code)]
     pub fn red() -> Self {
         Self::new(1.0, 0.0, 0.0, 1.0)
@@ -44,7 +61,106 @@ impl Color {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FillMode {
+    #[default]
+    None,
+    Solid,
+    Translucent,
+}
+
+impl FillMode {
+    pub const ALL: [Self; 3] = [Self::None, Self::Solid, Self::Translucent];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::None => "No fill",
+            Self::Solid => "Solid",
+            Self::Translucent => "Translucent",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DashStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl DashStyle {
+    pub const ALL: [Self; 3] = [Self::Solid, Self::Dashed, Self::Dotted];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Solid => "Solid",
+            Self::Dashed => "Dashed",
+            Self::Dotted => "Dotted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StrokeCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+impl StrokeCap {
+    pub const ALL: [Self; 3] = [Self::Butt, Self::Round, Self::Square];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Butt => "Butt cap",
+            Self::Round => "Round cap",
+            Self::Square => "Square cap",
+        }
+    }
+
+    fn to_cairo(self) -> cairo::LineCap {
+        match self {
+            Self::Butt => cairo::LineCap::Butt,
+            Self::Round => cairo::LineCap::Round,
+            Self::Square => cairo::LineCap::Square,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StrokeJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl StrokeJoin {
+    pub const ALL: [Self; 3] = [Self::Miter, Self::Round, Self::Bevel];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Miter => "Miter join",
+            Self::Round => "Round join",
+            Self::Bevel => "Bevel join",
+        }
+    }
+
+    fn to_cairo(self) -> cairo::LineJoin {
+        match self {
+            Self::Miter => cairo::LineJoin::Miter,
+            Self::Round => cairo::LineJoin::Round,
+            Self::Bevel => cairo::LineJoin::Bevel,
+        }
+    }
+}
+
+/// Alpha multiplier for `FillMode::Translucent`.
+const TRANSLUCENT_FILL: f64 = 0.35;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ShapeType {
     Rectangle,
     Ellipse,
@@ -54,9 +170,12 @@ pub enum ShapeType {
     Text,
     Blur,
     Highlight,
+    Marker,
+    Image,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Shape {
     pub shape_type: ShapeType,
     pub start_x: f64,
@@ -65,11 +184,25 @@ pub struct Shape {
     pub end_y: f64,
     pub color: Color,
     pub stroke_width: f64,
-    pub filled: bool,
+    pub fill: FillMode,
+    pub fill_color: Color,
+    pub dash: DashStyle,
+    pub cap: StrokeCap,
+    pub join: StrokeJoin,
+    pub corner_radius: f64,
     pub points: Vec<(f64, f64)>,
+    #[serde(with = "png_pixbuf")]
+    pub image: Option<Pixbuf>,
+    /// Visible part of `image` as `(x, y, width, height)` in image pixels.
+    pub crop: Option<(f64, f64, f64, f64)>,
     pub text: String,
     pub font_size: f64,
+    pub opacity: f64,
     pub rotation: f64,
+    pub group: Option<u32>,
+    pub name: String,
+    pub hidden: bool,
+    pub locked: bool,
 }
 
 impl Default for Shape {
@@ -82,29 +215,56 @@ impl Default for Shape {
             end_y: 0.0,
             color: Color::new(1.0, 0.0, 0.0, 1.0),
             stroke_width: 3.0,
-            filled: false,
+            fill: FillMode::None,
+            fill_color: Color::new(1.0, 0.0, 0.0, 1.0),
+            dash: DashStyle::Solid,
+            cap: StrokeCap::Butt,
+            join: StrokeJoin::Miter,
+            corner_radius: 0.0,
             points: Vec::new(),
+            image: None,
+            crop: None,
             text: String::new(),
             font_size: 20.0,
+            opacity: 1.0,
             rotation: 0.0,
+            group: None,
+            name: String::new(),
+            hidden: false,
+            locked: false,
         }
     }
 }
 
 impl Shape {
     pub fn draw(&self, ctx: &cairo::Context) {
+        if self.hidden {
+            return;
+        }
+
         let has_rotation = self.rotation.abs() > 0.001;
 
+        ctx.save().ok();
+
         if has_rotation {
             let (cx, cy) = self.center();
-            ctx.save().ok();
             ctx.translate(cx, cy);
             ctx.rotate(self.rotation);
             ctx.translate(-cx, -cy);
         }
 
+        // Marker strokes blend with what's beneath them, so they take the
+        // opacity in their own alpha instead of through a group.
+        let grouped = self.opacity < 1.0 && self.shape_type != ShapeType::Marker;
+        if grouped {
+            ctx.push_group();
+        }
+
         ctx.set_source_rgba(self.color.r, self.color.g, self.color.b, self.color.a);
         ctx.set_line_width(self.stroke_width);
+        ctx.set_line_cap(self.cap.to_cairo());
+        ctx.set_line_join(self.join.to_cairo());
+        ctx.set_dash(&self.dash_pattern(), 0.0);
 
         match self.shape_type {
             ShapeType::Rectangle => self.draw_rectangle(ctx),
@@ -115,11 +275,69 @@ impl Shape {
             ShapeType::Text => self.draw_text(ctx),
             ShapeType::Blur => self.draw_blur_placeholder(ctx),
             ShapeType::Highlight => self.draw_highlight(ctx),
+            ShapeType::Marker => self.draw_marker(ctx),
+            ShapeType::Image => self.draw_image(ctx),
         }
 
-        if has_rotation {
-            ctx.restore().ok();
+        if grouped {
+            let _ = ctx.pop_group_to_source();
+            let _ = ctx.paint_with_alpha(self.opacity);
+        }
+
+        ctx.restore().ok();
+    }
+
+    /// Dash lengths scaled to the stroke width. Dots are zero-length dashes,
+    /// which only show with round or square caps.
+    fn dash_pattern(&self) -> Vec<f64> {
+        let width = self.stroke_width.max(1.0);
+        match self.dash {
+            DashStyle::Solid => Vec::new(),
+            DashStyle::Dashed => vec![width * 3.0, width * 2.0],
+            DashStyle::Dotted if self.cap == StrokeCap::Butt => vec![width, width],
+            DashStyle::Dotted => vec![0.0, width * 2.0],
+        }
+    }
+
+    /// Fills the current path according to the fill mode, keeping it for
+    /// the outline stroke that follows.
+    fn fill_path(&self, ctx: &cairo::Context) {
+        let alpha = match self.fill {
+            FillMode::None => return,
+            FillMode::Solid => self.fill_color.a,
+            FillMode::Translucent => self.fill_color.a * TRANSLUCENT_FILL,
+        };
+
+        ctx.save().ok();
+        ctx.set_source_rgba(
+            self.fill_color.r,
+            self.fill_color.g,
+            self.fill_color.b,
+            alpha,
+        );
+        let _ = ctx.fill_preserve();
+        ctx.restore().ok();
+    }
+
+    fn rect_path(&self, ctx: &cairo::Context, x: f64, y: f64, width: f64, height: f64) {
+        let radius = self.corner_radius.min(width / 2.0).min(height / 2.0);
+        if radius <= 0.0 {
+            ctx.rectangle(x, y, width, height);
+            return;
         }
+
+        ctx.new_sub_path();
+        ctx.arc(x + width - radius, y + radius, radius, -PI / 2.0, 0.0);
+        ctx.arc(
+            x + width - radius,
+            y + height - radius,
+            radius,
+            0.0,
+            PI / 2.0,
+        );
+        ctx.arc(x + radius, y + height - radius, radius, PI / 2.0, PI);
+        ctx.arc(x + radius, y + radius, radius, PI, 1.5 * PI);
+        ctx.close_path();
     }
 
     fn draw_rectangle(&self, ctx: &cairo::Context) {
@@ -128,13 +346,9 @@ impl Shape {
         let width = (self.end_x - self.start_x).abs();
         let height = (self.end_y - self.start_y).abs();
 
-        ctx.rectangle(x, y, width, height);
-
-        if self.filled {
-            let _ = ctx.fill();
-        } else {
-            let _ = ctx.stroke();
-        }
+        self.rect_path(ctx, x, y, width, height);
+        self.fill_path(ctx);
+        let _ = ctx.stroke();
     }
 
     fn draw_ellipse(&self, ctx: &cairo::Context) {
@@ -150,11 +364,8 @@ impl Shape {
             ctx.arc(0.0, 0.0, 1.0, 0.0, 2.0 * PI);
             ctx.restore().ok();
 
-            if self.filled {
-                let _ = ctx.fill();
-            } else {
-                let _ = ctx.stroke();
-            }
+            self.fill_path(ctx);
+            let _ = ctx.stroke();
         }
     }
 
@@ -169,6 +380,9 @@ impl Shape {
         ctx.line_to(self.end_x, self.end_y);
         let _ = ctx.stroke();
 
+        // The head stays solid so it reads as an arrow with any dash style.
+        ctx.set_dash(&[], 0.0);
+
         let arrow_length = 15.0;
         let arrow_angle = PI / 6.0;
 
@@ -193,10 +407,7 @@ impl Shape {
             return;
         }
 
-        ctx.move_to(self.points[0].0, self.points[0].1);
-        for point in &self.points[1..] {
-            ctx.line_to(point.0, point.1);
-        }
+        catmull_rom_path(ctx, &self.points);
         let _ = ctx.stroke();
     }
 
@@ -207,6 +418,23 @@ impl Shape {
 
         ctx.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Bold);
         ctx.set_font_size(self.font_size);
+
+        if self.fill != FillMode::None {
+            let padding = self.font_size * 0.2;
+            let width = ctx
+                .text_extents(&self.text)
+                .map_or(0.0, |extents| extents.x_advance());
+            self.rect_path(
+                ctx,
+                self.start_x - padding,
+                self.start_y - padding,
+                width + padding * 2.0,
+                self.font_size * 1.25 + padding * 2.0,
+            );
+            self.fill_path(ctx);
+            ctx.new_path();
+        }
+
         ctx.move_to(self.start_x, self.start_y + self.font_size);
         let _ = ctx.show_text(&self.text);
     }
@@ -251,17 +479,118 @@ impl Shape {
         let height = (self.end_y - self.start_y).abs();
 
         ctx.set_source_rgba(self.color.r, self.color.g, self.color.b, 0.3);
-        ctx.rectangle(x, y, width, height);
+        self.rect_path(ctx, x, y, width, height);
         let _ = ctx.fill();
     }
 
+    fn draw_marker(&self, ctx: &cairo::Context) {
+        if self.points.is_empty() {
+            return;
+        }
+
+        // The whole stroke is a single path, so self-overlapping parts are
+        // covered once and don't darken twice.
+        ctx.save().ok();
+        ctx.set_line_cap(cairo::LineCap::Butt);
+        ctx.set_dash(&[], 0.0);
+        ctx.set_operator(cairo::Operator::Multiply);
+        ctx.set_source_rgba(self.color.r, self.color.g, self.color.b, 0.4 * self.opacity);
+        catmull_rom_path(ctx, &self.points);
+        let _ = ctx.stroke();
+        ctx.restore().ok();
+    }
+
+    fn draw_image(&self, ctx: &cairo::Context) {
+        let Some(ref image) = self.image else {
+            return;
+        };
+
+        let (x0, y0, x1, y1) = self.bounds();
+        let (sx, sy, sw, sh) = self.crop_rect(image);
+        if sw < 1.0 || sh < 1.0 || x1 - x0 < 1.0 || y1 - y0 < 1.0 {
+            return;
+        }
+
+        ctx.save().ok();
+        ctx.rectangle(x0, y0, x1 - x0, y1 - y0);
+        ctx.clip();
+        ctx.translate(x0, y0);
+        ctx.scale((x1 - x0) / sw, (y1 - y0) / sh);
+        ctx.set_source_pixbuf(image, -sx, -sy);
+        let _ = ctx.paint();
+        ctx.restore().ok();
+    }
+
+    fn crop_rect(&self, image: &Pixbuf) -> (f64, f64, f64, f64) {
+        self.crop
+            .unwrap_or((0.0, 0.0, image.width() as f64, image.height() as f64))
+    }
+
+    /// Moves a corner of an image like `resize_corner`, but trims or reveals
+    /// the picture instead of scaling it.
+    pub fn crop_corner(&mut self, corner: u8, new_x: f64, new_y: f64) {
+        let Some(ref image) = self.image else {
+            return;
+        };
+
+        let (x0, y0, x1, y1) = self.bounds();
+        let (cx, cy, cw, ch) = self.crop_rect(image);
+        let scale_x = (x1 - x0) / cw.max(1.0);
+        let scale_y = (y1 - y0) / ch.max(1.0);
+        if scale_x <= 0.0 || scale_y <= 0.0 {
+            return;
+        }
+
+        let left = x0 - cx * scale_x;
+        let top = y0 - cy * scale_y;
+        let right = left + image.width() as f64 * scale_x;
+        let bottom = top + image.height() as f64 * scale_y;
+
+        let (mut nx0, mut ny0, mut nx1, mut ny1) = (x0, y0, x1, y1);
+        match corner {
+            0 => (nx0, ny0) = (new_x, new_y),
+            1 => (nx1, ny0) = (new_x, new_y),
+            2 => (nx0, ny1) = (new_x, new_y),
+            3 => (nx1, ny1) = (new_x, new_y),
+            _ => return,
+        }
+
+        let nx0 = nx0.max(left).min(x1 - 1.0);
+        let ny0 = ny0.max(top).min(y1 - 1.0);
+        let nx1 = nx1.min(right).max(nx0 + 1.0);
+        let ny1 = ny1.min(bottom).max(ny0 + 1.0);
+
+        self.crop = Some((
+            cx + (nx0 - x0) / scale_x,
+            cy + (ny0 - y0) / scale_y,
+            (nx1 - nx0) / scale_x,
+            (ny1 - ny0) / scale_y,
+        ));
+        self.start_x = nx0;
+        self.start_y = ny0;
+        self.end_x = nx1;
+        self.end_y = ny1;
+    }
+
+    /// Whether the fill and corner radius settings apply to this shape.
+    pub fn is_fillable(&self) -> bool {
+        matches!(
+            self.shape_type,
+            ShapeType::Rectangle | ShapeType::Ellipse | ShapeType::Text
+        )
+    }
+
     pub fn add_point(&mut self, x: f64, y: f64) {
         self.points.push((x, y));
     }
 
+    pub fn simplify_points(&mut self, epsilon: f64) {
+        self.points = simplify(&self.points, epsilon);
+    }
+
     pub fn bounds(&self) -> (f64, f64, f64, f64) {
         match self.shape_type {
-            ShapeType::FreeHand => {
+            ShapeType::FreeHand | ShapeType::Marker => {
                 if self.points.is_empty() {
                     return (self.start_x, self.start_y, self.start_x, self.start_y);
                 }
@@ -313,7 +642,7 @@ impl Shape {
         let tolerance = self.stroke_width.max(5.0);
 
         match self.shape_type {
-            ShapeType::Rectangle | ShapeType::Blur | ShapeType::Highlight => {
+            ShapeType::Rectangle | ShapeType::Blur | ShapeType::Highlight | ShapeType::Image => {
                 let (min_x, min_y, max_x, max_y) = self.bounds();
                 x >= min_x - tolerance
                     && x <= max_x + tolerance
@@ -333,25 +662,18 @@ impl Shape {
                 (dx * dx) / (rx * rx) + (dy * dy) / (ry * ry) <= 1.0
             }
             ShapeType::Line | ShapeType::Arrow => {
-                let dist = Self::point_to_line_distance(
-                    x,
-                    y,
-                    self.start_x,
-                    self.start_y,
-                    self.end_x,
-                    self.end_y,
-                );
+                let dist =
+                    segment_distance(x, y, self.start_x, self.start_y, self.end_x, self.end_y);
                 dist <= tolerance
             }
-            ShapeType::FreeHand => {
-                for point in &self.points {
-                    let dx = x - point.0;
-                    let dy = y - point.1;
-                    if dx * dx + dy * dy <= tolerance * tolerance {
-                        return true;
-                    }
+            ShapeType::FreeHand | ShapeType::Marker => {
+                if let [(px, py)] = self.points[..] {
+                    return segment_distance(x, y, px, py, px, py) <= tolerance;
                 }
-                false
+                self.points.windows(2).any(|segment| {
+                    let ((x1, y1), (x2, y2)) = (segment[0], segment[1]);
+                    segment_distance(x, y, x1, y1, x2, y2) <= tolerance
+                })
             }
             ShapeType::Text => {
                 let (min_x, min_y, max_x, max_y) = self.bounds();
@@ -360,28 +682,6 @@ impl Shape {
         }
     }
 
-    fn point_to_line_distance(px: f64, py: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
-        let dx = x2 - x1;
-        let dy = y2 - y1;
-        let len_sq = dx * dx + dy * dy;
-
-        if len_sq < 0.0001 {
-            let ddx = px - x1;
-            let ddy = py - y1;
-            return (ddx * ddx + ddy * ddy).sqrt();
-        }
-
-        let t = ((px - x1) * dx + (py - y1) * dy) / len_sq;
-        let t = t.clamp(0.0, 1.0);
-
-        let proj_x = x1 + t * dx;
-        let proj_y = y1 + t * dy;
-
-        let ddx = px - proj_x;
-        let ddy = py - proj_y;
-        (ddx * ddx + ddy * ddy).sqrt()
-    }
-
     pub fn translate(&mut self, dx: f64, dy: f64) {
         self.start_x += dx;
         self.start_y += dy;
@@ -394,13 +694,29 @@ impl Shape {
         }
     }
 
+    /// Scales the shape's geometry by `(sx, sy)` around `(ox, oy)`. Text
+    /// scales its font by the vertical factor.
+    pub fn scale_from(&mut self, ox: f64, oy: f64, sx: f64, sy: f64) {
+        let scale = |x: f64, y: f64| (ox + (x - ox) * sx, oy + (y - oy) * sy);
+
+        (self.start_x, self.start_y) = scale(self.start_x, self.start_y);
+        (self.end_x, self.end_y) = scale(self.end_x, self.end_y);
+        for point in &mut self.points {
+            *point = scale(point.0, point.1);
+        }
+
+        if self.shape_type == ShapeType::Text {
+            self.font_size = (self.font_size * sy).clamp(6.0, 200.0);
+        }
+    }
+
     pub fn set_rotation(&mut self, angle: f64) {
         self.rotation = angle;
     }
 
     pub fn resize_corner(&mut self, corner: u8, new_x: f64, new_y: f64) {
         match self.shape_type {
-            ShapeType::FreeHand => {
+            ShapeType::FreeHand | ShapeType::Marker => {
                 self.resize_freehand_proportional(corner, new_x, new_y);
             }
             ShapeType::Text => {
@@ -506,3 +822,79 @@ impl Shape {
         }
     }
 }
+
+/// Stores images as PNG bytes so copied image layers survive the clipboard.
+mod png_pixbuf {
+    use gtk4::gdk_pixbuf::Pixbuf;
+    use gtk4::gio::{Cancellable, MemoryInputStream};
+    use gtk4::glib;
+    use serde::{de, ser, Deserialize, Deserializer, Serializer};
+
+    /// Images are stored as base64 PNG.
+    pub fn serialize<S: Serializer>(
+        image: &Option<Pixbuf>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let Some(pixbuf) = image else {
+            return serializer.serialize_none();
+        };
+        let png = pixbuf
+            .save_to_bufferv("png", &[])
+            .map_err(ser::Error::custom)?;
+        serializer.serialize_some(glib::base64_encode(&png).as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Pixbuf>, D::Error> {
+        let Some(text) = Option::<String>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        let bytes = glib::Bytes::from_owned(glib::base64_decode(&text));
+        let stream = MemoryInputStream::from_bytes(&bytes);
+        Pixbuf::from_stream(&stream, Cancellable::NONE)
+            .map(Some)
+            .map_err(|e| de::Error::custom(format!("invalid image: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gtk4::gdk_pixbuf::Colorspace;
+
+    fn image_shape() -> Shape {
+        let pixbuf = Pixbuf::new(Colorspace::Rgb, true, 8, 3, 2).unwrap();
+        pixbuf.fill(0x336699ff);
+        Shape {
+            shape_type: ShapeType::Image,
+            image: Some(pixbuf),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn images_serialize_as_base64_png() {
+        let json = serde_json::to_value(image_shape()).unwrap();
+        let text = json["image"].as_str().unwrap();
+
+        assert!(glib::base64_decode(text).starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn images_round_trip() {
+        let json = serde_json::to_string(&image_shape()).unwrap();
+        let shape: Shape = serde_json::from_str(&json).unwrap();
+        let image = shape.image.unwrap();
+
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert_eq!(&image.read_pixel_bytes()[..4], &[0x33, 0x66, 0x99, 0xff]);
+    }
+
+    #[test]
+    fn invalid_images_are_an_error() {
+        let json = r#"{ "shape_type": "Image", "image": "bm90IGEgcG5n" }"#;
+
+        assert!(serde_json::from_str::<Shape>(json).is_err());
+    }
+}
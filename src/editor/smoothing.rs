@@ -0,0 +1,184 @@
+const MIN_SAMPLE_DISTANCE: f64 = 0.75;
+
+/// Speed-adaptive low-pass filter for pointer samples, in the spirit of the
+/// one-euro filter: slow movements are smoothed heavily to remove jitter,
+/// fast movements follow the pointer closely to avoid lag.
+#[derive(Debug, Clone, Default)]
+pub struct StrokeSmoother {
+    level: f64,
+    last: Option<(f64, f64)>,
+}
+
+impl StrokeSmoother {
+    pub fn reset(&mut self, level: f64, x: f64, y: f64) {
+        self.level = level.clamp(0.0, 1.0);
+        self.last = Some((x, y));
+    }
+
+    /// Filters a raw sample. Returns `None` when the filtered point is too
+    /// close to the previous one to be worth storing.
+    pub fn push(&mut self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let Some((lx, ly)) = self.last else {
+            self.last = Some((x, y));
+            return Some((x, y));
+        };
+
+        let speed = ((x - lx).powi(2) + (y - ly).powi(2)).sqrt();
+        let min_alpha = 1.0 - 0.9 * self.level;
+        let alpha = (min_alpha + speed * 0.02).clamp(min_alpha, 1.0);
+
+        let sx = lx + (x - lx) * alpha;
+        let sy = ly + (y - ly) * alpha;
+
+        if (sx - lx).powi(2) + (sy - ly).powi(2) < MIN_SAMPLE_DISTANCE * MIN_SAMPLE_DISTANCE {
+            return None;
+        }
+
+        self.last = Some((sx, sy));
+        Some((sx, sy))
+    }
+}
+
+/// Tolerance used by `simplify` for a given smoothing level in `0.0..=1.0`.
+pub fn simplify_epsilon(level: f64) -> f64 {
+    0.5 + level.clamp(0.0, 1.0) * 2.5
+}
+
+/// Ramer–Douglas–Peucker polyline simplification.
+pub fn simplify(points: &[(f64, f64)], epsilon: f64) -> Vec<(f64, f64)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        if last <= first + 1 {
+            continue;
+        }
+
+        let (ax, ay) = points[first];
+        let (bx, by) = points[last];
+        let mut max_dist = 0.0;
+        let mut max_idx = first;
+
+        for (idx, &(px, py)) in points.iter().enumerate().take(last).skip(first + 1) {
+            let dist = segment_distance(px, py, ax, ay, bx, by);
+            if dist > max_dist {
+                max_dist = dist;
+                max_idx = idx;
+            }
+        }
+
+        if max_dist > epsilon {
+            keep[max_idx] = true;
+            stack.push((first, max_idx));
+            stack.push((max_idx, last));
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(point, kept)| kept.then_some(*point))
+        .collect()
+}
+
+/// Adds the path of a Catmull-Rom spline through `points` to `ctx` as cubic
+/// Bézier segments.
+pub fn catmull_rom_path(ctx: &cairo::Context, points: &[(f64, f64)]) {
+    let Some(&(x0, y0)) = points.first() else {
+        return;
+    };
+
+    ctx.move_to(x0, y0);
+
+    if points.len() == 1 {
+        ctx.line_to(x0, y0);
+        return;
+    }
+
+    let last = points.len() - 1;
+    for i in 0..last {
+        let p0 = points[i.saturating_sub(1)];
+        let p1 = points[i];
+        let p2 = points[i + 1];
+        let p3 = points[(i + 2).min(last)];
+
+        let c1 = (p1.0 + (p2.0 - p0.0) / 6.0, p1.1 + (p2.1 - p0.1) / 6.0);
+        let c2 = (p2.0 - (p3.0 - p1.0) / 6.0, p2.1 - (p3.1 - p1.1) / 6.0);
+
+        ctx.curve_to(c1.0, c1.1, c2.0, c2.1, p2.0, p2.1);
+    }
+}
+
+pub fn segment_distance(px: f64, py: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+    let dx = x2 - x1;
+    let dy = y2 - y1;
+    let len_sq = dx * dx + dy * dy;
+
+    if len_sq < 0.0001 {
+        let ddx = px - x1;
+        let ddy = py - y1;
+        return (ddx * ddx + ddy * ddy).sqrt();
+    }
+
+    let t = (((px - x1) * dx + (py - y1) * dy) / len_sq).clamp(0.0, 1.0);
+
+    let ddx = px - (x1 + t * dx);
+    let ddy = py - (y1 + t * dy);
+    (ddx * ddx + ddy * ddy).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simplify_drops_points_on_a_straight_line() {
+        let points: Vec<_> = (0..=10).map(|i| (i as f64, i as f64 * 2.0)).collect();
+
+        assert_eq!(simplify(&points, 0.5), vec![(0.0, 0.0), (10.0, 20.0)]);
+    }
+
+    #[test]
+    fn simplify_keeps_corners_beyond_epsilon() {
+        let points = [(0.0, 0.0), (5.0, 0.1), (10.0, 0.0), (10.0, 10.0)];
+
+        assert_eq!(
+            simplify(&points, 0.5),
+            vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]
+        );
+        assert_eq!(simplify(&points, 0.05), points.to_vec());
+    }
+
+    #[test]
+    fn simplify_leaves_short_strokes_alone() {
+        assert_eq!(simplify(&[], 1.0), Vec::new());
+        assert_eq!(simplify(&[(1.0, 2.0)], 1.0), vec![(1.0, 2.0)]);
+        assert_eq!(
+            simplify(&[(0.0, 0.0), (3.0, 4.0)], 100.0),
+            vec![(0.0, 0.0), (3.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn segment_distance_clamps_to_the_ends() {
+        assert_eq!(segment_distance(5.0, 3.0, 0.0, 0.0, 10.0, 0.0), 3.0);
+        assert_eq!(segment_distance(13.0, 4.0, 0.0, 0.0, 10.0, 0.0), 5.0);
+        assert_eq!(segment_distance(3.0, 4.0, 0.0, 0.0, 0.0, 0.0), 5.0);
+    }
+
+    #[test]
+    fn smoother_skips_samples_that_barely_move() {
+        let mut smoother = StrokeSmoother::default();
+        smoother.reset(1.0, 0.0, 0.0);
+
+        assert_eq!(smoother.push(0.5, 0.0), None);
+        let (x, _) = smoother.push(100.0, 0.0).unwrap();
+        assert!(x > 0.0 && x <= 100.0);
+    }
+}
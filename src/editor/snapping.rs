@@ -0,0 +1,188 @@
+use super::shapes::Shape;
+
+/// Snap distance in screen pixels; divided by the zoom level before use.
+pub const SNAP_DISTANCE: f64 = 6.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapGuide {
+    Vertical(f64),
+    Horizontal(f64),
+}
+
+/// Edges and centers that a moving or resized shape can snap to.
+#[derive(Debug, Default)]
+pub struct SnapTargets {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    grid: Option<f64>,
+}
+
+impl SnapTargets {
+    pub fn new(
+        shapes: &[Shape],
+        skip: &[usize],
+        image_size: Option<(f64, f64)>,
+        grid: Option<f64>,
+    ) -> Self {
+        let mut targets = Self {
+            grid: grid.filter(|size| *size >= 1.0),
+            ..Default::default()
+        };
+
+        if let Some((width, height)) = image_size {
+            targets.add_box(0.0, 0.0, width, height);
+        }
+
+        for (idx, shape) in shapes.iter().enumerate() {
+            if skip.contains(&idx) {
+                continue;
+            }
+            let (min_x, min_y, max_x, max_y) = shape.bounds();
+            targets.add_box(min_x, min_y, max_x, max_y);
+        }
+
+        targets
+    }
+
+    fn add_box(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) {
+        self.xs.extend([min_x, (min_x + max_x) / 2.0, max_x]);
+        self.ys.extend([min_y, (min_y + max_y) / 2.0, max_y]);
+    }
+
+    /// Snaps a box being moved by `(dx, dy)`. Returns the adjusted offset and
+    /// the guides to display.
+    pub fn snap_box(
+        &self,
+        bounds: (f64, f64, f64, f64),
+        dx: f64,
+        dy: f64,
+        threshold: f64,
+    ) -> ((f64, f64), Vec<SnapGuide>) {
+        let (min_x, min_y, max_x, max_y) = bounds;
+        let xs = [min_x + dx, (min_x + max_x) / 2.0 + dx, max_x + dx];
+        let ys = [min_y + dy, (min_y + max_y) / 2.0 + dy, max_y + dy];
+
+        let mut guides = Vec::new();
+        let snap_x = self.snap_axis(&xs, &self.xs, threshold);
+        let snap_y = self.snap_axis(&ys, &self.ys, threshold);
+
+        if let Some(Snap { guide: Some(x), .. }) = snap_x {
+            guides.push(SnapGuide::Vertical(x));
+        }
+        if let Some(Snap { guide: Some(y), .. }) = snap_y {
+            guides.push(SnapGuide::Horizontal(y));
+        }
+
+        (
+            (
+                dx + snap_x.map_or(0.0, |snap| snap.delta),
+                dy + snap_y.map_or(0.0, |snap| snap.delta),
+            ),
+            guides,
+        )
+    }
+
+    /// Snaps a single dragged point, such as a resize handle.
+    pub fn snap_point(&self, x: f64, y: f64, threshold: f64) -> ((f64, f64), Vec<SnapGuide>) {
+        let ((dx, dy), guides) = self.snap_box((x, y, x, y), 0.0, 0.0, threshold);
+        ((x + dx, y + dy), guides)
+    }
+
+    fn snap_axis(&self, candidates: &[f64], targets: &[f64], threshold: f64) -> Option<Snap> {
+        let mut best: Option<Snap> = None;
+        let mut consider = |delta: f64, guide: Option<f64>| {
+            if delta.abs() <= threshold && best.is_none_or(|snap| delta.abs() < snap.delta.abs()) {
+                best = Some(Snap { delta, guide });
+            }
+        };
+
+        for &value in candidates {
+            for &target in targets {
+                consider(target - value, Some(target));
+            }
+
+            if let Some(size) = self.grid {
+                consider((value / size).round() * size - value, None);
+            }
+        }
+
+        best
+    }
+}
+
+/// Offset that aligns a candidate with its target; grid snaps have no guide.
+#[derive(Debug, Clone, Copy)]
+struct Snap {
+    delta: f64,
+    guide: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::shapes::ShapeType;
+
+    fn rectangle(x0: f64, y0: f64, x1: f64, y1: f64) -> Shape {
+        Shape {
+            shape_type: ShapeType::Rectangle,
+            start_x: x0,
+            start_y: y0,
+            end_x: x1,
+            end_y: y1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn moving_box_snaps_to_other_shapes() {
+        let shapes = [
+            rectangle(0.0, 0.0, 50.0, 50.0),
+            rectangle(100.0, 0.0, 200.0, 50.0),
+        ];
+        let targets = SnapTargets::new(&shapes, &[0], None, None);
+
+        let ((dx, dy), guides) = targets.snap_box(shapes[0].bounds(), 47.0, 2.0, 6.0);
+
+        assert_eq!((dx, dy), (50.0, 0.0));
+        assert_eq!(
+            guides,
+            vec![SnapGuide::Vertical(100.0), SnapGuide::Horizontal(0.0)]
+        );
+    }
+
+    #[test]
+    fn nothing_snaps_beyond_the_threshold() {
+        let shapes = [rectangle(100.0, 100.0, 200.0, 200.0)];
+        let targets = SnapTargets::new(&shapes, &[], None, None);
+
+        assert_eq!(
+            targets.snap_point(90.0, 250.0, 6.0),
+            ((90.0, 250.0), Vec::new())
+        );
+    }
+
+    #[test]
+    fn points_snap_to_image_edges_and_center() {
+        let targets = SnapTargets::new(&[], &[], Some((800.0, 600.0)), None);
+
+        assert_eq!(
+            targets.snap_point(797.0, 301.0, 6.0),
+            (
+                (800.0, 300.0),
+                vec![SnapGuide::Vertical(800.0), SnapGuide::Horizontal(300.0)]
+            )
+        );
+    }
+
+    #[test]
+    fn grid_snaps_without_guides() {
+        let targets = SnapTargets::new(&[], &[], None, Some(10.0));
+        assert_eq!(
+            targets.snap_point(13.0, 27.0, 4.0),
+            ((10.0, 30.0), Vec::new())
+        );
+
+        let tiny = SnapTargets::new(&[], &[], None, Some(0.5));
+        assert_eq!(tiny.snap_point(13.2, 27.3, 4.0), ((13.2, 27.3), Vec::new()));
+    }
+}
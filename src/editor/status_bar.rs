@@ -0,0 +1,154 @@
+use gtk4::prelude::*;
+use gtk4::{Box, DrawingArea, Label, Orientation, Separator};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use super::canvas::EditorCanvas;
+use super::shapes::Color;
+
+/// Strip under the canvas showing the image size, zoom, pointer position,
+/// the color under the pointer and the selection's geometry.
+pub struct StatusBar {
+    root: Box,
+    canvas: EditorCanvas,
+    size: Label,
+    zoom: Label,
+    pointer: Label,
+    swatch: DrawingArea,
+    color: Label,
+    selection: Label,
+    pixel: Rc<Cell<Option<Color>>>,
+}
+
+impl StatusBar {
+    pub fn new(canvas: &EditorCanvas) -> Rc<Self> {
+        let root = Box::new(Orientation::Horizontal, 12);
+        root.add_css_class("status-bar");
+
+        let size = Label::new(None);
+        let zoom = Label::new(None);
+        let pointer = Label::new(None);
+        pointer.set_width_chars(16);
+        pointer.set_xalign(0.0);
+
+        let pixel: Rc<Cell<Option<Color>>> = Rc::default();
+        let swatch = DrawingArea::new();
+        swatch.set_content_width(12);
+        swatch.set_content_height(12);
+        swatch.set_valign(gtk4::Align::Center);
+        let swatch_pixel = pixel.clone();
+        swatch.set_draw_func(move |_, ctx, width, height| {
+            if let Some(color) = swatch_pixel.get() {
+                ctx.set_source_rgb(color.r, color.g, color.b);
+                ctx.rectangle(0.0, 0.0, width as f64, height as f64);
+                let _ = ctx.fill();
+            }
+        });
+
+        let color = Label::new(None);
+        color.set_width_chars(24);
+        color.set_xalign(0.0);
+
+        let selection = Label::new(None);
+        selection.set_hexpand(true);
+        selection.set_xalign(1.0);
+
+        root.append(&size);
+        root.append(&Separator::new(Orientation::Vertical));
+        root.append(&zoom);
+        root.append(&Separator::new(Orientation::Vertical));
+        root.append(&pointer);
+        root.append(&swatch);
+        root.append(&color);
+        root.append(&selection);
+
+        let bar = Rc::new(Self {
+            root,
+            canvas: canvas.clone(),
+            size,
+            zoom,
+            pointer,
+            swatch,
+            color,
+            selection,
+            pixel,
+        });
+
+        let weak = Rc::downgrade(&bar);
+        canvas.connect_view_changed(move |_| {
+            if let Some(bar) = weak.upgrade() {
+                bar.sync_view();
+            }
+        });
+
+        let weak = Rc::downgrade(&bar);
+        canvas.connect_selection_changed(move |_| {
+            if let Some(bar) = weak.upgrade() {
+                bar.sync_selection();
+            }
+        });
+
+        let weak = Rc::downgrade(&bar);
+        canvas.connect_shapes_changed(move |_| {
+            if let Some(bar) = weak.upgrade() {
+                bar.sync_selection();
+            }
+        });
+
+        bar.sync_view();
+        bar.sync_selection();
+        bar
+    }
+
+    pub fn widget(&self) -> &Box {
+        &self.root
+    }
+
+    fn sync_view(&self) {
+        match self.canvas.image_size() {
+            Some((width, height)) => self.size.set_text(&format!("{} × {} px", width, height)),
+            None => self.size.set_text("No image"),
+        }
+        self.zoom
+            .set_text(&format!("{:.0}%", self.canvas.get_zoom() * 100.0));
+
+        let position = self.canvas.pointer_position();
+        match position {
+            Some((x, y)) => self
+                .pointer
+                .set_text(&format!("{:.0}, {:.0}", x.floor(), y.floor())),
+            None => self.pointer.set_text(""),
+        }
+
+        let pixel = position.and_then(|(x, y)| self.canvas.pixel_at(x, y));
+        self.pixel.set(pixel);
+        self.swatch.set_visible(pixel.is_some());
+        match pixel {
+            Some(color) => {
+                let (r, g, b) = color.to_rgb8();
+                self.color
+                    .set_text(&format!("{}  rgb({}, {}, {})", color.to_hex(), r, g, b));
+            }
+            None => self.color.set_text(""),
+        }
+        self.swatch.queue_draw();
+    }
+
+    fn sync_selection(&self) {
+        let Some((x, y, width, height)) = self.canvas.selection_geometry() else {
+            self.selection.set_text("");
+            return;
+        };
+
+        let mut text = format!("Selection {:.0}, {:.0}  {:.0} × {:.0}", x, y, width, height);
+        if self.canvas.selection().len() == 1 {
+            if let Some(shape) = self.canvas.selected_shape() {
+                let degrees = (shape.rotation.to_degrees() + 180.0).rem_euclid(360.0) - 180.0;
+                if degrees.abs() >= 0.5 {
+                    text.push_str(&format!("  {:.0}°", degrees));
+                }
+            }
+        }
+        self.selection.set_text(&text);
+    }
+}
@@ -0,0 +1,104 @@
+use gtk4::gdk::RGBA;
+use gtk4::prelude::*;
+use gtk4::{Box, Button, DrawingArea, Orientation};
+use std::rc::Rc;
+
+use super::shapes::Color;
+
+/// Toolbar strip of the configured palette followed by recently used
+/// colors. Clicking a swatch activates `win.set-color` with its color.
+pub struct ColorSwatches {
+    root: Box,
+    recent: Box,
+    palette: Vec<RGBA>,
+}
+
+impl ColorSwatches {
+    /// `palette` holds CSS colors, e.g. `#e01b24`; unparsable ones are
+    /// skipped.
+    pub fn new(palette: &[String]) -> Rc<Self> {
+        let root = Box::new(Orientation::Horizontal, 2);
+        root.add_css_class("swatches");
+        root.set_valign(gtk4::Align::Center);
+
+        let palette: Vec<RGBA> = palette
+            .iter()
+            .filter_map(|color| RGBA::parse(color).ok())
+            .collect();
+        for color in &palette {
+            root.append(&swatch_button(color));
+        }
+
+        let recent = Box::new(Orientation::Horizontal, 2);
+        recent.add_css_class("recent-colors");
+        root.append(&recent);
+
+        Rc::new(Self {
+            root,
+            recent,
+            palette,
+        })
+    }
+
+    pub fn widget(&self) -> &Box {
+        &self.root
+    }
+
+    /// Palette color bound to number key `number`, counting from 1.
+    pub fn palette_color(&self, number: usize) -> Option<RGBA> {
+        number
+            .checked_sub(1)
+            .and_then(|idx| self.palette.get(idx))
+            .copied()
+    }
+
+    pub fn in_palette(&self, color: &RGBA) -> bool {
+        self.palette.contains(color)
+    }
+
+    pub fn set_recent(&self, colors: &[RGBA]) {
+        while let Some(child) = self.recent.first_child() {
+            self.recent.remove(&child);
+        }
+        for color in colors {
+            self.recent.append(&swatch_button(color));
+        }
+        self.recent.set_visible(!colors.is_empty());
+    }
+}
+
+fn swatch_button(color: &RGBA) -> Button {
+    let fill = *color;
+    let area = DrawingArea::new();
+    area.set_content_width(14);
+    area.set_content_height(14);
+    area.set_draw_func(move |_, ctx, width, height| {
+        ctx.rectangle(0.5, 0.5, width as f64 - 1.0, height as f64 - 1.0);
+        ctx.set_source_rgba(
+            fill.red() as f64,
+            fill.green() as f64,
+            fill.blue() as f64,
+            fill.alpha() as f64,
+        );
+        let _ = ctx.fill_preserve();
+        ctx.set_source_rgba(0.5, 0.5, 0.5, 0.8);
+        ctx.set_line_width(1.0);
+        let _ = ctx.stroke();
+    });
+
+    let hex = Color::new(
+        color.red() as f64,
+        color.green() as f64,
+        color.blue() as f64,
+        color.alpha() as f64,
+    )
+    .to_hex();
+
+    let button = Button::new();
+    button.set_child(Some(&area));
+    button.add_css_class("swatch");
+    button.set_tooltip_text(Some(&hex));
+    button.set_action_name(Some("win.set-color"));
+    button.set_action_target_value(Some(&color.to_str().to_variant()));
+    button
+}
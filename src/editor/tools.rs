@@ -1,4 +1,7 @@
-use super::shapes::{Color, Shape, ShapeType};
+use super::shapes::{Color, DashStyle, FillMode, Shape, ShapeType, StrokeCap, StrokeJoin};
+
+const MARKER_WIDTH_SCALE: f64 = 4.0;
+const MARKER_MIN_WIDTH: f64 = 12.0;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ToolType {
@@ -12,14 +15,24 @@ pub enum ToolType {
     Text,
     Blur,
     Highlight,
+    Marker,
+    /// Picks a color from the screenshot instead of drawing.
+    Eyedropper,
 }
 
 pub struct Tool {
     pub tool_type: ToolType,
     pub color: Color,
     pub stroke_width: f64,
-    pub filled: bool,
+    pub fill: FillMode,
+    pub fill_color: Color,
+    pub dash: DashStyle,
+    pub cap: StrokeCap,
+    pub join: StrokeJoin,
+    pub corner_radius: f64,
     pub font_size: f64,
+    pub opacity: f64,
+    pub smoothing: f64,
 }
 
 impl Default for Tool {
@@ -28,8 +41,15 @@ impl Default for Tool {
             tool_type: ToolType::Select,
             color: Color::new(1.0, 0.0, 0.0, 1.0),
             stroke_width: 3.0,
-            filled: false,
+            fill: FillMode::None,
+            fill_color: Color::new(1.0, 0.0, 0.0, 1.0),
+            dash: DashStyle::Solid,
+            cap: StrokeCap::Butt,
+            join: StrokeJoin::Miter,
+            corner_radius: 0.0,
             font_size: 20.0,
+            opacity: 1.0,
+            smoothing: 0.5,
         }
     }
 }
@@ -60,8 +80,44 @@ impl Tool {
             end_y,
             color: self.color,
             stroke_width: self.stroke_width,
-            filled: self.filled,
+            fill: self.fill,
+            fill_color: self.fill_color,
+            dash: self.dash,
+            cap: self.cap,
+            join: self.join,
+            corner_radius: self.corner_radius,
             font_size: self.font_size,
+            opacity: self.opacity,
+            ..Default::default()
+        })
+    }
+
+    pub fn create_stroke_shape(&self, x: f64, y: f64) -> Option<Shape> {
+        // The highlighter is always a solid, flat-ended band.
+        let (shape_type, stroke_width, dash, cap) = match self.tool_type {
+            ToolType::FreeHand => (ShapeType::FreeHand, self.stroke_width, self.dash, self.cap),
+            ToolType::Marker => (
+                ShapeType::Marker,
+                shape_stroke_width(ShapeType::Marker, self.stroke_width),
+                DashStyle::Solid,
+                StrokeCap::Butt,
+            ),
+            _ => return None,
+        };
+
+        Some(Shape {
+            shape_type,
+            start_x: x,
+            start_y: y,
+            end_x: x,
+            end_y: y,
+            color: self.color,
+            stroke_width,
+            dash,
+            cap,
+            join: self.join,
+            opacity: self.opacity,
+            points: vec![(x, y)],
             ..Default::default()
         })
     }
@@ -75,10 +131,74 @@ impl Tool {
             end_y: y,
             color: self.color,
             stroke_width: self.stroke_width,
-            filled: false,
+            fill: self.fill,
+            fill_color: self.fill_color,
+            corner_radius: self.corner_radius,
             font_size: self.font_size,
+            opacity: self.opacity,
             text,
             ..Default::default()
         }
     }
 }
+
+/// Width a shape of `shape_type` is stroked with for a toolbar stroke width.
+pub fn shape_stroke_width(shape_type: ShapeType, width: f64) -> f64 {
+    match shape_type {
+        ShapeType::Marker => (width * MARKER_WIDTH_SCALE).max(MARKER_MIN_WIDTH),
+        _ => width,
+    }
+}
+
+/// Toolbar stroke width that best matches `shape`.
+pub fn tool_stroke_width(shape: &Shape) -> f64 {
+    match shape.shape_type {
+        ShapeType::Marker => shape.stroke_width / MARKER_WIDTH_SCALE,
+        _ => shape.stroke_width,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_strokes_are_solid_and_flat_ended() {
+        let tool = Tool {
+            tool_type: ToolType::Marker,
+            dash: DashStyle::Dashed,
+            cap: StrokeCap::Round,
+            ..Default::default()
+        };
+        let shape = tool.create_stroke_shape(0.0, 0.0).unwrap();
+
+        assert_eq!(shape.dash, DashStyle::Solid);
+        assert_eq!(shape.cap, StrokeCap::Butt);
+    }
+
+    #[test]
+    fn freehand_strokes_keep_the_tool_style() {
+        let tool = Tool {
+            tool_type: ToolType::FreeHand,
+            dash: DashStyle::Dashed,
+            cap: StrokeCap::Square,
+            ..Default::default()
+        };
+        let shape = tool.create_stroke_shape(0.0, 0.0).unwrap();
+
+        assert_eq!(shape.dash, DashStyle::Dashed);
+        assert_eq!(shape.cap, StrokeCap::Square);
+    }
+
+    #[test]
+    fn shapes_default_to_butt_caps_and_miter_joins() {
+        let tool = Tool {
+            tool_type: ToolType::Rectangle,
+            ..Default::default()
+        };
+        let shape = tool.create_shape(0.0, 0.0, 10.0, 10.0).unwrap();
+
+        assert_eq!(shape.cap, StrokeCap::Butt);
+        assert_eq!(shape.join, StrokeJoin::Miter);
+    }
+}
@@ -0,0 +1,169 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::imageops::FilterType as ResizeFilter;
+use std::fs;
+use std::path::Path;
+
+use crate::config::ImageFormat;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+impl PngCompression {
+    pub const ALL: [PngCompression; 3] = [
+        PngCompression::Fast,
+        PngCompression::Default,
+        PngCompression::Best,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PngCompression::Fast => "Fast",
+            PngCompression::Default => "Default",
+            PngCompression::Best => "Smallest",
+        }
+    }
+
+    fn to_image(self) -> CompressionType {
+        match self {
+            PngCompression::Fast => CompressionType::Fast,
+            PngCompression::Default => CompressionType::Default,
+            PngCompression::Best => CompressionType::Best,
+        }
+    }
+}
+
+/// How a rendered screenshot is written to disk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportOptions {
+    pub format: ImageFormat,
+    /// JPEG quality, 1 to 100.
+    pub quality: u8,
+    pub compression: PngCompression,
+    /// Factor the image is resized by before encoding.
+    pub scale: f64,
+}
+
+impl ExportOptions {
+    pub fn new(format: ImageFormat) -> Self {
+        Self {
+            format,
+            quality: 90,
+            compression: PngCompression::default(),
+            scale: 1.0,
+        }
+    }
+}
+
+/// Re-encodes the PNG `data` rendered by the canvas according to `options`.
+pub fn encode(data: &[u8], options: &ExportOptions) -> anyhow::Result<Vec<u8>> {
+    if options.format == ImageFormat::Png
+        && options.compression == PngCompression::default()
+        && options.scale == 1.0
+    {
+        return Ok(data.to_vec());
+    }
+
+    let mut image = image::load_from_memory(data)?;
+    if options.scale != 1.0 {
+        let width = ((image.width() as f64 * options.scale).round() as u32).max(1);
+        let height = ((image.height() as f64 * options.scale).round() as u32).max(1);
+        image = image.resize_exact(width, height, ResizeFilter::Lanczos3);
+    }
+
+    let mut out = Vec::new();
+    match options.format {
+        ImageFormat::Png => image.write_with_encoder(PngEncoder::new_with_quality(
+            &mut out,
+            options.compression.to_image(),
+            FilterType::Adaptive,
+        ))?,
+        ImageFormat::Jpg => image
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(
+                &mut out,
+                options.quality.clamp(1, 100),
+            ))?,
+    }
+    Ok(out)
+}
+
+pub fn write(path: &Path, data: &[u8], options: &ExportOptions) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, encode(data, options)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat as Format, Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(width, height, Rgba([200, 40, 40, 255]));
+        let mut data = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut data), Format::Png)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn default_png_is_passed_through() {
+        let data = png(4, 4);
+
+        assert_eq!(
+            encode(&data, &ExportOptions::new(ImageFormat::Png)).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn scale_resizes_before_encoding() {
+        let options = ExportOptions {
+            scale: 0.5,
+            ..ExportOptions::new(ImageFormat::Png)
+        };
+
+        let image = image::load_from_memory(&encode(&png(10, 6), &options).unwrap()).unwrap();
+        assert_eq!((image.width(), image.height()), (5, 3));
+
+        let options = ExportOptions {
+            scale: 0.01,
+            ..options
+        };
+        let image = image::load_from_memory(&encode(&png(10, 6), &options).unwrap()).unwrap();
+        assert_eq!((image.width(), image.height()), (1, 1));
+    }
+
+    #[test]
+    fn jpeg_is_encoded_as_jpeg() {
+        let options = ExportOptions {
+            quality: 0,
+            ..ExportOptions::new(ImageFormat::Jpg)
+        };
+
+        let data = encode(&png(8, 8), &options).unwrap();
+        assert_eq!(image::guess_format(&data).unwrap(), Format::Jpeg);
+    }
+
+    #[test]
+    fn png_compression_reencodes() {
+        let options = ExportOptions {
+            compression: PngCompression::Best,
+            ..ExportOptions::new(ImageFormat::Png)
+        };
+
+        let data = encode(&png(8, 8), &options).unwrap();
+        assert_eq!(image::guess_format(&data).unwrap(), Format::Png);
+        assert!(encode(b"not an image", &options).is_err());
+    }
+}
@@ -1,13 +1,19 @@
 mod app;
 mod capture;
+mod clipboard;
+mod commands;
 mod config;
 mod editor;
+mod export;
+mod notify;
+mod palette;
+mod session;
+mod toast;
 
 use anyhow::Result;
 use capture::{CaptureBackend, CaptureMode};
 use clap::Parser;
-use std::io::Write;
-use std::process::{Command, Stdio};
+use notify::Notifier;
 
 #[derive(Parser)]
 #[command(name = "niri-shot")]
@@ -22,11 +28,33 @@ struct Args {
 
     #[arg(short, long, help = "Capture window")]
     window: bool,
+
+    #[arg(long, help = "Pick a color from the screen and print it as #rrggbb")]
+    pick_color: bool,
+}
+
+/// Reports an error from before the editor starts as a notification, since
+/// stderr is not seen when launched from a compositor keybind.
+fn notify_error(summary: &str, error: &dyn std::fmt::Display) {
+    let notifier = Notifier::new();
+    notifier.error(summary, error);
+    notifier.flush();
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
+    if args.pick_color {
+        match CaptureBackend::pick_color() {
+            Ok(hex) => println!("{}", hex),
+            Err(e) => {
+                eprintln!("Pick color error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
     let initial_mode = if args.fullscreen {
         Some(CaptureMode::Fullscreen)
     } else if args.region {
@@ -40,11 +68,15 @@ fn main() -> Result<()> {
     let initial_data = if let Some(mode) = initial_mode {
         match CaptureBackend::capture(mode) {
             Ok(data) => {
-                copy_to_clipboard(&data);
+                if let Err(e) = clipboard::copy_with_wl_copy(&data) {
+                    eprintln!("Failed to copy to clipboard: {}", e);
+                    notify_error("Copy failed", &e);
+                }
                 Some(data)
             }
             Err(e) => {
                 eprintln!("Capture error: {}", e);
+                notify_error("Capture failed", &e);
                 return Ok(());
             }
         }
@@ -56,21 +88,3 @@ fn main() -> Result<()> {
     app.run();
     Ok(())
 }
-
-fn copy_to_clipboard(data: &[u8]) {
-    let result = Command::new("wl-copy")
-        .arg("--type")
-        .arg("image/png")
-        .stdin(Stdio::piped())
-        .spawn()
-        .and_then(|mut child| {
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin.write_all(data)?;
-            }
-            child.wait()
-        });
-
-    if result.is_err() {
-        eprintln!("Failed to copy to clipboard");
-    }
-}
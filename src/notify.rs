@@ -0,0 +1,425 @@
+use gtk4::gdk_pixbuf::Pixbuf;
+use gtk4::gio;
+use gtk4::glib;
+use gtk4::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const BUS_NAME: &str = "org.freedesktop.Notifications";
+const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+const INTERFACE: &str = "org.freedesktop.Notifications";
+const APP_NAME: &str = "niri-shot";
+const DESKTOP_ENTRY: &str = "com.github.niri-shot";
+const THUMBNAIL_SIZE: i32 = 256;
+const URGENCY_CRITICAL: u8 = 2;
+
+/// A button on a notification and what it does when clicked.
+pub struct NotificationAction {
+    pub label: &'static str,
+    pub run: Box<dyn Fn()>,
+}
+
+impl NotificationAction {
+    pub fn new(label: &'static str, run: impl Fn() + 'static) -> Self {
+        Self {
+            label,
+            run: Box::new(run),
+        }
+    }
+}
+
+type PendingActions = Rc<RefCell<HashMap<u32, Vec<NotificationAction>>>>;
+
+/// Desktop notifications through `org.freedesktop.Notifications` on the
+/// session bus. Does nothing when there is no bus.
+pub struct Notifier {
+    connection: Option<gio::DBusConnection>,
+    pending: PendingActions,
+}
+
+impl Notifier {
+    pub fn new() -> Rc<Self> {
+        let connection = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE)
+            .map_err(|e| eprintln!("Notifications unavailable: {}", e))
+            .ok();
+        Self::with_connection(connection)
+    }
+
+    fn with_connection(connection: Option<gio::DBusConnection>) -> Rc<Self> {
+        let pending = PendingActions::default();
+
+        if let Some(ref connection) = connection {
+            let invoked = pending.clone();
+            connection.signal_subscribe(
+                None,
+                Some(INTERFACE),
+                Some("ActionInvoked"),
+                Some(OBJECT_PATH),
+                None,
+                gio::DBusSignalFlags::NONE,
+                move |_, _, _, _, _, params| {
+                    let Some((id, key)) = params.get::<(u32, String)>() else {
+                        return;
+                    };
+                    let Some(actions) = invoked.borrow_mut().remove(&id) else {
+                        return;
+                    };
+                    if let Some(action) = invoked_action(&actions, &key) {
+                        (action.run)();
+                    }
+                },
+            );
+
+            let closed = pending.clone();
+            connection.signal_subscribe(
+                None,
+                Some(INTERFACE),
+                Some("NotificationClosed"),
+                Some(OBJECT_PATH),
+                None,
+                gio::DBusSignalFlags::NONE,
+                move |_, _, _, _, _, params| {
+                    if let Some((id, _reason)) = params.get::<(u32, u32)>() {
+                        closed.borrow_mut().remove(&id);
+                    }
+                },
+            );
+        }
+
+        Rc::new(Self {
+            connection,
+            pending,
+        })
+    }
+
+    /// Shows a notification, with a thumbnail of the PNG `image` if given.
+    pub fn notify(
+        &self,
+        summary: &str,
+        body: &str,
+        image: Option<&[u8]>,
+        actions: Vec<NotificationAction>,
+    ) {
+        let mut hints = HashMap::new();
+        if let Some(thumbnail) = image.and_then(thumbnail_hint) {
+            hints.insert("image-data".to_string(), thumbnail);
+        }
+        self.send(summary, body, hints, actions);
+    }
+
+    /// Shows an error that stays until dismissed.
+    pub fn error(&self, summary: &str, error: &dyn std::fmt::Display) {
+        let mut hints = HashMap::new();
+        hints.insert("urgency".to_string(), URGENCY_CRITICAL.to_variant());
+        self.send(summary, &error.to_string(), hints, Vec::new());
+    }
+
+    /// Waits until queued notifications have gone out, for when the process
+    /// exits without running a main loop.
+    pub fn flush(&self) {
+        if let Some(ref connection) = self.connection {
+            if let Err(e) = connection.flush_sync(gio::Cancellable::NONE) {
+                eprintln!("Notification error: {}", e);
+            }
+        }
+    }
+
+    fn send(
+        &self,
+        summary: &str,
+        body: &str,
+        mut hints: HashMap<String, glib::Variant>,
+        actions: Vec<NotificationAction>,
+    ) {
+        let Some(ref connection) = self.connection else {
+            return;
+        };
+
+        hints.insert("desktop-entry".to_string(), DESKTOP_ENTRY.to_variant());
+        let params = notify_params(summary, body, &actions, hints);
+
+        let pending = self.pending.clone();
+        connection.call(
+            Some(BUS_NAME),
+            OBJECT_PATH,
+            INTERFACE,
+            "Notify",
+            Some(&params),
+            Some(glib::VariantTy::new("(u)").expect("valid type")),
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+            move |result| match result {
+                Ok(reply) => {
+                    if let Some((id,)) = reply.get::<(u32,)>() {
+                        if !actions.is_empty() {
+                            pending.borrow_mut().insert(id, actions);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Notification error: {}", e),
+            },
+        );
+    }
+}
+
+/// Arguments of the `Notify` method. Actions are sent as (key, label)
+/// pairs; the key is the index into `actions`.
+fn notify_params(
+    summary: &str,
+    body: &str,
+    actions: &[NotificationAction],
+    hints: HashMap<String, glib::Variant>,
+) -> glib::Variant {
+    let action_list: Vec<String> = actions
+        .iter()
+        .enumerate()
+        .flat_map(|(i, action)| [i.to_string(), action.label.to_string()])
+        .collect();
+
+    (APP_NAME, 0u32, "", summary, body, action_list, hints, -1i32).to_variant()
+}
+
+/// The action whose key `ActionInvoked` reported.
+fn invoked_action<'a>(
+    actions: &'a [NotificationAction],
+    key: &str,
+) -> Option<&'a NotificationAction> {
+    key.parse::<usize>().ok().and_then(|i| actions.get(i))
+}
+
+/// The `image-data` hint, `(iiibiiay)`, for a thumbnail of the PNG `data`.
+fn thumbnail_hint(data: &[u8]) -> Option<glib::Variant> {
+    let bytes = glib::Bytes::from(data);
+    let stream = gio::MemoryInputStream::from_bytes(&bytes);
+    let pixbuf = Pixbuf::from_stream_at_scale(
+        &stream,
+        THUMBNAIL_SIZE,
+        THUMBNAIL_SIZE,
+        true,
+        gio::Cancellable::NONE,
+    )
+    .ok()?;
+
+    let pixels = pixbuf.read_pixel_bytes().to_vec();
+    Some(
+        (
+            pixbuf.width(),
+            pixbuf.height(),
+            pixbuf.rowstride(),
+            pixbuf.has_alpha(),
+            pixbuf.bits_per_sample(),
+            pixbuf.n_channels(),
+            pixels,
+        )
+            .to_variant(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn actions() -> Vec<NotificationAction> {
+        vec![
+            NotificationAction::new("Open in editor", || {}),
+            NotificationAction::new("Open folder", || {}),
+        ]
+    }
+
+    #[test]
+    fn notify_params_match_the_spec() {
+        let params = notify_params("Saved", "/tmp/shot.png", &actions(), HashMap::new());
+
+        assert_eq!(params.type_().as_str(), "(susssasa{sv}i)");
+        let (app, replaces, icon, summary, body, actions, _hints, timeout) = params
+            .get::<(
+                String,
+                u32,
+                String,
+                String,
+                String,
+                Vec<String>,
+                HashMap<String, glib::Variant>,
+                i32,
+            )>()
+            .unwrap();
+        assert_eq!(app, APP_NAME);
+        assert_eq!(replaces, 0);
+        assert_eq!(icon, "");
+        assert_eq!(summary, "Saved");
+        assert_eq!(body, "/tmp/shot.png");
+        assert_eq!(actions, ["0", "Open in editor", "1", "Open folder"]);
+        assert_eq!(timeout, -1);
+    }
+
+    #[test]
+    fn invoked_keys_map_to_actions() {
+        let actions = actions();
+
+        assert_eq!(
+            invoked_action(&actions, "1").map(|a| a.label),
+            Some("Open folder")
+        );
+        assert!(invoked_action(&actions, "2").is_none());
+        assert!(invoked_action(&actions, "default").is_none());
+    }
+
+    const DAEMON_XML: &str = r#"
+        <node>
+          <interface name="org.freedesktop.Notifications">
+            <method name="Notify">
+              <arg type="s" direction="in"/>
+              <arg type="u" direction="in"/>
+              <arg type="s" direction="in"/>
+              <arg type="s" direction="in"/>
+              <arg type="s" direction="in"/>
+              <arg type="as" direction="in"/>
+              <arg type="a{sv}" direction="in"/>
+              <arg type="i" direction="in"/>
+              <arg type="u" direction="out"/>
+            </method>
+          </interface>
+        </node>"#;
+
+    /// A private message bus, stopped when dropped.
+    struct TestBus(std::process::Child);
+
+    impl TestBus {
+        fn start() -> Option<(Self, String)> {
+            use std::io::BufRead;
+
+            let mut child = std::process::Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(std::process::Stdio::piped())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            std::io::BufReader::new(child.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some((Self(child), address.trim().to_string()))
+        }
+    }
+
+    impl Drop for TestBus {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    fn connect(address: &str) -> gio::DBusConnection {
+        gio::DBusConnection::for_address_sync(
+            address,
+            gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+            None,
+            gio::Cancellable::NONE,
+        )
+        .unwrap()
+    }
+
+    fn iterate_until(context: &glib::MainContext, done: impl Fn() -> bool) {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !done() {
+            assert!(std::time::Instant::now() < deadline, "timed out");
+            context.iteration(false);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+    }
+
+    /// Sends a notification to a stand-in daemon on a private bus, then has
+    /// the daemon report a click on its second button.
+    #[test]
+    fn works_with_a_notification_daemon() {
+        let Some((_bus, address)) = TestBus::start() else {
+            eprintln!("dbus-daemon not found, skipping");
+            return;
+        };
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let daemon = connect(&address);
+                let received: Rc<RefCell<Option<glib::Variant>>> = Rc::default();
+                let interface = gio::DBusNodeInfo::for_xml(DAEMON_XML)
+                    .unwrap()
+                    .lookup_interface(INTERFACE)
+                    .unwrap();
+                let calls = received.clone();
+                daemon
+                    .register_object(OBJECT_PATH, &interface)
+                    .method_call(move |_, _, _, _, _, params, invocation| {
+                        *calls.borrow_mut() = Some(params);
+                        invocation.return_value(Some(&(7u32,).to_variant()));
+                    })
+                    .build()
+                    .unwrap();
+                daemon
+                    .call_sync(
+                        Some("org.freedesktop.DBus"),
+                        "/org/freedesktop/DBus",
+                        "org.freedesktop.DBus",
+                        "RequestName",
+                        Some(&(BUS_NAME, 0u32).to_variant()),
+                        None,
+                        gio::DBusCallFlags::NONE,
+                        -1,
+                        gio::Cancellable::NONE,
+                    )
+                    .unwrap();
+
+                let notifier = Notifier::with_connection(Some(connect(&address)));
+                let clicked = Rc::new(Cell::new(None));
+                let actions = ["Open in editor", "Open folder"]
+                    .into_iter()
+                    .map(|label| {
+                        let clicked = clicked.clone();
+                        NotificationAction::new(label, move || clicked.set(Some(label)))
+                    })
+                    .collect();
+                notifier.notify("Saved", "shot.png", None, actions);
+
+                iterate_until(&context, || {
+                    received.borrow().is_some() && notifier.pending.borrow().contains_key(&7)
+                });
+                let params = received.borrow().clone().unwrap();
+                assert_eq!(params.child_value(3).str(), Some("Saved"));
+                let hints = params
+                    .child_value(6)
+                    .get::<HashMap<String, glib::Variant>>();
+                assert_eq!(
+                    hints.and_then(|hints| hints.get("desktop-entry")?.get::<String>()),
+                    Some(DESKTOP_ENTRY.to_string())
+                );
+
+                daemon
+                    .emit_signal(
+                        None,
+                        OBJECT_PATH,
+                        INTERFACE,
+                        "ActionInvoked",
+                        Some(&(7u32, "1").to_variant()),
+                    )
+                    .unwrap();
+                iterate_until(&context, || clicked.get().is_some());
+                assert_eq!(clicked.get(), Some("Open folder"));
+                assert!(notifier.pending.borrow().is_empty());
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn invoked_action_runs_its_callback() {
+        let clicked = Rc::new(Cell::new(false));
+        let flag = clicked.clone();
+        let actions = vec![NotificationAction::new("Delete", move || flag.set(true))];
+
+        if let Some(action) = invoked_action(&actions, "0") {
+            (action.run)();
+        }
+        assert!(clicked.get());
+    }
+}
@@ -0,0 +1,195 @@
+use gtk4::prelude::*;
+use gtk4::{gio, glib};
+use gtk4::{
+    ApplicationWindow, Box, EventControllerKey, Label, ListBox, ListBoxRow, Orientation,
+    ScrolledWindow, SearchEntry, SelectionMode,
+};
+use std::rc::Rc;
+
+use crate::toast::Toasts;
+
+/// One line of the command palette.
+pub struct PaletteEntry {
+    pub title: String,
+    pub section: String,
+    pub accel: Option<String>,
+    /// Detailed window action run when the entry is chosen.
+    pub action: String,
+}
+
+impl PaletteEntry {
+    fn matches(&self, query: &str) -> bool {
+        let haystack = format!("{} {}", self.section, self.title).to_lowercase();
+        query
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| haystack.contains(word))
+    }
+}
+
+/// Opens a searchable list of `entries` over `parent`. Enter or a click runs
+/// the highlighted entry, Escape closes the palette. Commands that fail are
+/// reported on `toasts`.
+pub fn show(parent: &ApplicationWindow, entries: Vec<PaletteEntry>, toasts: &Toasts) {
+    let entries = Rc::new(entries);
+
+    let search = SearchEntry::new();
+    search.set_placeholder_text(Some("Type a command…"));
+
+    let list = ListBox::new();
+    list.set_selection_mode(SelectionMode::Browse);
+    list.set_activate_on_single_click(true);
+
+    for entry in entries.iter() {
+        let line = Box::new(Orientation::Horizontal, 12);
+
+        let title = Label::new(Some(&entry.title));
+        title.set_xalign(0.0);
+        title.set_hexpand(true);
+        title.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+
+        let section = Label::new(Some(&entry.section));
+        section.add_css_class("dim-label");
+
+        line.append(&title);
+        line.append(&section);
+
+        if let Some(accel) = &entry.accel {
+            let accel = Label::new(Some(accel));
+            accel.add_css_class("dim-label");
+            accel.set_width_chars(12);
+            accel.set_xalign(1.0);
+            line.append(&accel);
+        }
+
+        let row = ListBoxRow::new();
+        row.set_child(Some(&line));
+        list.append(&row);
+    }
+
+    let filter_entries = entries.clone();
+    let filter_search = search.clone();
+    list.set_filter_func(move |row| {
+        filter_entries
+            .get(row.index() as usize)
+            .is_some_and(|entry| entry.matches(&filter_search.text()))
+    });
+
+    let scrolled = ScrolledWindow::new();
+    scrolled.set_child(Some(&list));
+    scrolled.set_vexpand(true);
+    list.set_adjustment(Some(&scrolled.vadjustment()));
+
+    let content = Box::new(Orientation::Vertical, 8);
+    content.add_css_class("command-palette");
+    content.append(&search);
+    content.append(&scrolled);
+
+    let window = gtk4::Window::builder()
+        .transient_for(parent)
+        .modal(true)
+        .decorated(false)
+        .default_width(560)
+        .default_height(420)
+        .child(&content)
+        .build();
+
+    let run = {
+        let window = window.downgrade();
+        let parent = parent.downgrade();
+        let toasts = toasts.clone();
+        Rc::new(move |row: &ListBoxRow| {
+            let Some(entry) = entries.get(row.index() as usize) else {
+                return;
+            };
+            if let Some(window) = window.upgrade() {
+                window.close();
+            }
+            let Some(parent) = parent.upgrade() else {
+                return;
+            };
+            match gio::Action::parse_detailed_name(&entry.action) {
+                Ok((name, target)) => {
+                    if let Err(e) = WidgetExt::activate_action(&parent, &name, target.as_ref()) {
+                        toasts.error("Could not run command", &e);
+                    }
+                }
+                Err(e) => toasts.error("Could not run command", &e),
+            }
+        })
+    };
+
+    let row_run = run.clone();
+    list.connect_row_activated(move |_, row| row_run(row));
+
+    let activate_list = list.clone();
+    search.connect_activate(move |_| {
+        if let Some(row) = activate_list.selected_row() {
+            run(&row);
+        }
+    });
+
+    let changed_list = list.clone();
+    search.connect_search_changed(move |_| {
+        changed_list.invalidate_filter();
+        select_next(&changed_list, None, 1);
+    });
+
+    let stop_window = window.downgrade();
+    search.connect_stop_search(move |_| {
+        if let Some(window) = stop_window.upgrade() {
+            window.close();
+        }
+    });
+
+    let key_controller = EventControllerKey::new();
+    let key_list = list.clone();
+    key_controller.connect_key_pressed(move |_, key, _, _| {
+        let step = match key {
+            gtk4::gdk::Key::Down => 1,
+            gtk4::gdk::Key::Up => -1,
+            _ => return glib::Propagation::Proceed,
+        };
+        select_next(&key_list, key_list.selected_row(), step);
+        glib::Propagation::Stop
+    });
+    search.add_controller(key_controller);
+
+    select_next(&list, None, 1);
+    window.present();
+    search.grab_focus();
+}
+
+/// Selects the first visible row after `from` in direction `step`, or the
+/// first visible row when `from` is `None`. Focus stays in the search entry,
+/// so the list is scrolled by hand.
+fn select_next(list: &ListBox, from: Option<ListBoxRow>, step: i32) {
+    let mut index = from.map_or(0, |row| row.index() + step);
+
+    while index >= 0 {
+        let Some(row) = list.row_at_index(index) else {
+            return;
+        };
+        if row.is_child_visible() {
+            list.select_row(Some(&row));
+            scroll_to_row(list, &row);
+            return;
+        }
+        index += step;
+    }
+}
+
+fn scroll_to_row(list: &ListBox, row: &ListBoxRow) {
+    let (Some(adjustment), Some((_, y))) =
+        (list.adjustment(), row.translate_coordinates(list, 0.0, 0.0))
+    else {
+        return;
+    };
+    let height = row.height() as f64;
+
+    if y < adjustment.value() {
+        adjustment.set_value(y);
+    } else if y + height > adjustment.value() + adjustment.page_size() {
+        adjustment.set_value(y + height - adjustment.page_size());
+    }
+}
@@ -0,0 +1,135 @@
+use gtk4::gdk_pixbuf::Pixbuf;
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config::Config;
+
+const IMAGE_FILE: &str = "image.png";
+const SHAPES_FILE: &str = "shapes.json";
+
+/// Each running editor autosaves into `sessions/<pid>` in the cache
+/// directory, so several windows don't overwrite each other and a folder
+/// whose process is gone was left behind by a crash.
+fn sessions_dir() -> Option<PathBuf> {
+    Config::cache_dir().map(|dir| dir.join("sessions"))
+}
+
+/// Periodic copy of the open screenshot and its annotations.
+pub struct Autosave {
+    dir: Option<PathBuf>,
+    /// Image already on disk; it only changes on capture or open, so it is
+    /// not rewritten with every edit.
+    image: RefCell<Option<Pixbuf>>,
+    written: Cell<bool>,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Autosave {
+    pub fn new() -> Self {
+        Self {
+            dir: sessions_dir().map(|dir| dir.join(std::process::id().to_string())),
+            image: RefCell::new(None),
+            written: Cell::new(false),
+        }
+    }
+
+    pub fn write(&self, image: &Pixbuf, shapes: &str) -> anyhow::Result<()> {
+        let Some(ref dir) = self.dir else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir)?;
+
+        if self.image.borrow().as_ref() != Some(image) {
+            let data = image.save_to_bufferv("png", &[])?;
+            write_atomically(&dir.join(IMAGE_FILE), &data)?;
+            *self.image.borrow_mut() = Some(image.clone());
+        }
+        write_atomically(&dir.join(SHAPES_FILE), shapes.as_bytes())?;
+
+        self.written.set(true);
+        Ok(())
+    }
+
+    /// Removes the autosave, once the work is saved or deliberately dropped.
+    pub fn clear(&self) {
+        if !self.written.replace(false) {
+            return;
+        }
+        self.image.borrow_mut().take();
+        if let Some(ref dir) = self.dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// An autosave left behind by an editor that did not close cleanly.
+pub struct Session {
+    dir: PathBuf,
+    pub image: Vec<u8>,
+    pub shapes: String,
+    pub modified: Option<SystemTime>,
+}
+
+impl Session {
+    /// The newest autosave whose process is no longer running. Older ones
+    /// are deleted.
+    pub fn find_orphaned() -> Option<Self> {
+        let entries = fs::read_dir(sessions_dir()?).ok()?;
+
+        let mut sessions: Vec<Session> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|dir| !is_running(dir))
+            .filter_map(Self::load)
+            .collect();
+
+        sessions.sort_by_key(|session| session.modified);
+        let newest = sessions.pop();
+        for stale in sessions {
+            stale.remove();
+        }
+        newest
+    }
+
+    fn load(dir: PathBuf) -> Option<Self> {
+        let image = fs::read(dir.join(IMAGE_FILE)).ok()?;
+        let shapes_path = dir.join(SHAPES_FILE);
+        let shapes = fs::read_to_string(&shapes_path).ok()?;
+        let modified = fs::metadata(&shapes_path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        Some(Self {
+            dir,
+            image,
+            shapes,
+            modified,
+        })
+    }
+
+    pub fn remove(&self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn is_running(dir: &Path) -> bool {
+    dir.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.parse::<u32>().ok())
+        .is_some_and(|pid| Path::new("/proc").join(pid.to_string()).exists())
+}
+
+/// Writes through a temporary file, so a crash mid-write leaves the previous
+/// autosave intact.
+fn write_atomically(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, data)?;
+    fs::rename(temp, path)?;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use gtk4::glib;
+use gtk4::prelude::*;
+use gtk4::{Box, Button, Label, Orientation};
+use std::time::Duration;
+
+const TOAST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Short-lived messages stacked over the top of the canvas, each with
+/// optional buttons bound to window actions.
+#[derive(Clone)]
+pub struct Toasts {
+    root: Box,
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        let root = Box::new(Orientation::Vertical, 6);
+        root.set_halign(gtk4::Align::Center);
+        root.set_valign(gtk4::Align::Start);
+        root.set_margin_top(12);
+        Self { root }
+    }
+
+    pub fn widget(&self) -> &Box {
+        &self.root
+    }
+
+    /// Shows `message` with a button per `(label, detailed action name)`.
+    pub fn show(&self, message: &str, buttons: &[(&str, String)]) {
+        self.append(message, buttons);
+    }
+
+    /// Shows a failed operation as `summary: error`.
+    pub fn error(&self, summary: &str, error: &dyn std::fmt::Display) {
+        let toast = self.append(&format!("{}: {}", summary, error), &[]);
+        toast.add_css_class("error");
+    }
+
+    fn append(&self, message: &str, buttons: &[(&str, String)]) -> Box {
+        let toast = Box::new(Orientation::Horizontal, 8);
+        toast.add_css_class("toast");
+
+        let label = Label::new(Some(message));
+        label.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+        label.set_max_width_chars(60);
+        toast.append(&label);
+
+        for (title, action) in buttons {
+            let button = Button::with_label(title);
+            button.set_detailed_action_name(action);
+            toast.append(&button);
+        }
+
+        let close = Button::from_icon_name("window-close-symbolic");
+        close.add_css_class("flat");
+        toast.append(&close);
+
+        let mut child = toast.first_child();
+        while let Some(widget) = child {
+            if let Some(button) = widget.downcast_ref::<Button>() {
+                let weak = toast.downgrade();
+                button.connect_clicked(move |_| {
+                    if let Some(toast) = weak.upgrade() {
+                        Self::dismiss(&toast);
+                    }
+                });
+            }
+            child = widget.next_sibling();
+        }
+
+        self.root.append(&toast);
+
+        let weak = toast.downgrade();
+        glib::timeout_add_local_once(TOAST_TIMEOUT, move || {
+            if let Some(toast) = weak.upgrade() {
+                Self::dismiss(&toast);
+            }
+        });
+
+        toast
+    }
+
+    fn dismiss(toast: &Box) {
+        if let Some(parent) = toast.parent().and_downcast::<Box>() {
+            parent.remove(toast);
+        }
+    }
+}